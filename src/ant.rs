@@ -0,0 +1,124 @@
+//! Langton's ant, a second automaton walking on the cells of a universe:
+//! at each step it turns right on a dead cell and left on a live one,
+//! flips the cell, and moves one cell forward.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Error, Universe};
+
+/// Which way Langton's ant is facing.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Heading {
+    Up = 0,
+    Right = 1,
+    Down = 2,
+    Left = 3,
+}
+
+impl Heading {
+    fn turned(self, right: bool) -> Heading {
+        let headings = [Heading::Up, Heading::Right, Heading::Down, Heading::Left];
+        headings[(self as usize + if right { 1 } else { 3 }) % 4]
+    }
+}
+
+// Position and heading of the ant on a universe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct LangtonAnt {
+    pub row: u32,
+    pub column: u32,
+    pub heading: Heading,
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Put Langton's ant on the cell at `(row, column)`, facing `heading`,
+    /// replacing the ant already there if any. The ant only moves with
+    /// `step_ant`, independently of `tick`, and is removed when the
+    /// universe is resized or transposed.
+    ///
+    /// Fails if the cell is outside of the universe.
+    pub fn place_ant(&mut self, row: u32, column: u32, heading: Heading) -> Result<(), Error> {
+        self.checked_index(row, column)?;
+        self.ant = Some(LangtonAnt { row, column, heading });
+        Ok(())
+    }
+
+    pub fn remove_ant(&mut self) {
+        self.ant = None;
+    }
+
+    /// Row of the cell the ant is on, if there is one, e.g. to draw it.
+    pub fn ant_row(&self) -> Option<u32> {
+        self.ant.map(|ant| ant.row)
+    }
+
+    /// Column of the cell the ant is on, if there is one.
+    pub fn ant_column(&self) -> Option<u32> {
+        self.ant.map(|ant| ant.column)
+    }
+
+    pub fn ant_heading(&self) -> Option<Heading> {
+        self.ant.map(|ant| ant.heading)
+    }
+
+    /// Move the ant by one step: turn right if its cell is dead and left
+    /// if it is alive, flip the cell, and move forward, wrapping around the
+    /// edges whatever the `boundary`. Does nothing without an ant.
+    ///
+    /// This is not a tick: the generation counter and the statistics of
+    /// `tick` are left as they are.
+    pub fn step_ant(&mut self) {
+        let ant = match self.ant {
+            Some(ant) => ant,
+            None => return,
+        };
+
+        let idx = self.get_index(ant.row, ant.column);
+        let heading = ant.heading.turned(!self.cells[idx]);
+        self.cells.toggle(idx);
+        self.edited = true;
+
+        let (row, column) = match heading {
+            Heading::Up => ((ant.row + self.height - 1) % self.height, ant.column),
+            Heading::Right => (ant.row, (ant.column + 1) % self.width),
+            Heading::Down => ((ant.row + 1) % self.height, ant.column),
+            Heading::Left => (ant.row, (ant.column + self.width - 1) % self.width),
+        };
+        self.ant = Some(LangtonAnt { row, column, heading });
+    }
+}
+
+#[test]
+fn test_step_ant() {
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    universe.step_ant();
+    assert_eq!(universe.population(), 0);
+
+    universe.place_ant(4, 4, Heading::Up).unwrap();
+    universe.step_ant();
+    assert_eq!(universe.live_cells(), vec![(4, 4)]);
+    assert_eq!((universe.ant_row(), universe.ant_column()), (Some(4), Some(5)));
+    assert_eq!(universe.ant_heading(), Some(Heading::Right));
+
+    // After 4 steps on dead cells the ant is back where it started, facing
+    // the same way, and it turns left on the live cell it left there.
+    for _ in 0..3 {
+        universe.step_ant();
+    }
+    assert_eq!(universe.live_cells(), vec![(4, 4), (4, 5), (5, 4), (5, 5)]);
+    assert_eq!((universe.ant_row(), universe.ant_column()), (Some(4), Some(4)));
+    universe.step_ant();
+    assert_eq!(universe.ant_heading(), Some(Heading::Left));
+    assert!(!universe.get_cell(4, 4).unwrap());
+
+    // It wraps around the edges.
+    universe.place_ant(0, 0, Heading::Left).unwrap();
+    universe.step_ant();
+    assert_eq!((universe.ant_row(), universe.ant_column()), (Some(7), Some(0)));
+
+    assert!(universe.place_ant(8, 0, Heading::Up).is_err());
+    universe.set_width(4);
+    assert_eq!(universe.ant_row(), None);
+}
@@ -0,0 +1,57 @@
+//! Headless native simulation runner.
+//!
+//! Runs a seeded universe for a number of generations without a browser and
+//! prints population statistics, e.g.
+//!
+//! ```text
+//! cargo run --bin simulate -- 128 128 1000 42
+//! ```
+//!
+//! The arguments are, in order and all optional: width, height, number of
+//! generations and seed. This is only built for native targets.
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    use std::env;
+    use std::process;
+
+    use wasm_game_of_life::Universe;
+
+    let defaults = [64u64, 64, 100, 0];
+    let mut params = defaults;
+    for (i, arg) in env::args().skip(1).take(params.len()).enumerate() {
+        params[i] = match arg.parse() {
+            Ok(value) => value,
+            Err(_) => {
+                eprintln!("usage: simulate [width] [height] [generations] [seed]");
+                process::exit(2);
+            }
+        };
+    }
+    let [width, height, generations, seed] = params;
+
+    let mut universe = Universe::new_seeded(width as u32, height as u32, seed);
+    let initial = universe.population();
+    let (mut min, mut max, mut total) = (initial, initial, 0u64);
+
+    for _ in 0..generations {
+        universe.tick();
+        let population = universe.population();
+        min = min.min(population);
+        max = max.max(population);
+        total += population as u64;
+    }
+
+    println!("universe:    {}x{}, seed {}", width, height, seed);
+    println!("generations: {}", generations);
+    println!("initial:     {}", initial);
+    println!("final:       {}", universe.population());
+    println!("min:         {}", min);
+    println!("max:         {}", max);
+    if generations > 0 {
+        println!("mean:        {:.2}", total as f64 / generations as f64);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -0,0 +1,98 @@
+//! Errors returned by the methods that validate their input, instead of
+//! panicking, which would abort the whole wasm module.
+
+use std::fmt;
+
+use wasm_bindgen::JsValue;
+
+/// Why a method rejected its input.
+///
+/// Methods exported to JavaScript throw it as an `Error` whose message is the
+/// `Display` text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A cell coordinate outside of the universe.
+    OutOfBounds {
+        row: u32,
+        column: u32,
+        width: u32,
+        height: u32,
+    },
+    /// A rule that is not in B/S notation.
+    InvalidRule(String),
+    /// Two universes that should have the same dimensions but do not.
+    DimensionMismatch {
+        width: u32,
+        height: u32,
+        other_width: u32,
+        other_height: u32,
+    },
+    /// Two universes that should follow the same rule but do not, both
+    /// given in the notation of `Universe::rule`.
+    RuleMismatch {
+        rule: String,
+        other_rule: String,
+    },
+    /// A universe too small for what it should hold.
+    TooSmall {
+        width: u32,
+        height: u32,
+        min_width: u32,
+        min_height: u32,
+    },
+    /// A buffer with the wrong number of values of some kind.
+    WrongLength {
+        what: &'static str,
+        expected: u64,
+        found: u64,
+    },
+    /// Bytes that are not a valid serialized universe, or text that is not
+    /// a valid pattern.
+    InvalidData(&'static str),
+    /// A line of an edit script that is not a known edit, numbered from 1.
+    InvalidScript {
+        line: usize,
+        text: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::OutOfBounds { row, column, width, height } => write!(
+                f,
+                "cell ({}, {}) is outside of a {}x{} universe",
+                row, column, width, height
+            ),
+            Error::InvalidRule(rule) => write!(f, "invalid rule: {}", rule),
+            Error::DimensionMismatch { width, height, other_width, other_height } => write!(
+                f,
+                "a {}x{} universe does not match a {}x{} one",
+                width, height, other_width, other_height
+            ),
+            Error::RuleMismatch { rule, other_rule } => {
+                write!(f, "a universe following {} does not match one following {}", rule, other_rule)
+            }
+            Error::TooSmall { width, height, min_width, min_height } => write!(
+                f,
+                "a universe of at least {}x{} cells is needed, not {}x{}",
+                min_width, min_height, width, height
+            ),
+            Error::WrongLength { what, expected, found } => {
+                write!(f, "expected {} {}, got {}", expected, what, found)
+            }
+            Error::InvalidData(reason) => f.write_str(reason),
+            Error::InvalidScript { line, text } => {
+                write!(f, "invalid edit on line {} of the script: {}", line, text)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for JsValue {
+    fn from(error: Error) -> JsValue {
+        js_sys::Error::new(&error.to_string()).into()
+    }
+}
@@ -0,0 +1,1032 @@
+//! Import and export of the universe in the usual Life pattern file formats.
+
+use wasm_bindgen::prelude::*;
+
+use fixedbitset::FixedBitSet;
+
+use crate::{Error, Universe};
+
+// Longest line written by `to_rle`, as recommended by the format.
+const RLE_LINE_LENGTH: usize = 70;
+
+// First byte of the blobs produced by `to_bytes` and `to_bytes_compressed`,
+// so that one can't be mistaken for the other.
+const BYTES_MAGIC: u8 = b'L';
+const COMPRESSED_BYTES_MAGIC: u8 = b'Z';
+
+// Most cells in a universe read by `from_bytes` and `from_bytes_compressed`,
+// so that a few bytes of header and runs can't make them allocate hundreds
+// of megabytes: 8 MiB of bits, as many as on an 8192x8192 board.
+const MAX_BYTES_CELLS: u64 = 1 << 26;
+
+// Digits of the standard base64 alphabet, used in data URLs, and of the
+// URL-safe one used by `to_base64`, which only differ in the last two.
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// Largest width or height of the patterns read by `parse_rle`, and most live
+// cells in them, so that a short but malformed or hostile text can't make it
+// allocate gigabytes.
+const MAX_RLE_SIZE: u32 = 1 << 16;
+const MAX_RLE_CELLS: usize = 1 << 24;
+
+// Largest width or height of the images made by `thumbnail_data_url`.
+const MAX_THUMBNAIL_SIZE: u32 = 256;
+
+// Largest width or height of the animations made by `to_gif`, and most
+// pixels in all of their frames together, to bound the time taken to encode
+// them and, for boards that don't compress well, the size of the result.
+const MAX_GIF_SIZE: u32 = 1024;
+const MAX_GIF_PIXELS: u64 = 64 * 1024 * 1024;
+
+#[wasm_bindgen]
+impl Universe {
+    /// Build a `width` by `height` universe from a pattern in the Life 1.05
+    /// format.
+    ///
+    /// Each `#P x y` line starts a block of `.` (dead) and `*` (alive) rows
+    /// whose top-left cell is `x` columns and `y` rows away from the center
+    /// of the universe, as in the format's convention; rows before the first
+    /// `#P` line are placed at the center. Every block is applied, wrapping
+    /// around the edges if needed. Other `#` lines, such as `#D`
+    /// descriptions and the `#Life 1.05` header, are skipped, and so are
+    /// malformed `#P` lines.
+    pub fn from_life105(text: &str, width: u32, height: u32) -> Universe {
+        let size = (width * height) as usize;
+        let mut universe = Universe::from_bitset(width, height, FixedBitSet::with_capacity(size));
+        if size == 0 {
+            return universe;
+        }
+
+        let (center_row, center_col) = ((height / 2) as i64, (width / 2) as i64);
+        let (mut block_row, mut block_col) = (0i64, 0i64);
+        let mut row = 0i64;
+
+        for line in text.lines().map(str::trim) {
+            if let Some(offsets) = line.strip_prefix("#P") {
+                let offsets: Vec<i64> = offsets
+                    .split_whitespace()
+                    .filter_map(|n| n.parse().ok())
+                    .collect();
+                if let [x, y] = offsets[..] {
+                    block_col = x;
+                    block_row = y;
+                    row = 0;
+                }
+                continue;
+            }
+            if line.starts_with('#') || line.is_empty() {
+                continue;
+            }
+
+            for (col, symbol) in line.chars().enumerate() {
+                if symbol == '*' {
+                    let r = (center_row + block_row + row).rem_euclid(height as i64);
+                    let c = (center_col + block_col + col as i64).rem_euclid(width as i64);
+                    let idx = universe.get_index(r as u32, c as u32);
+                    universe.cells.set(idx, true);
+                }
+            }
+            row += 1;
+        }
+
+        universe
+    }
+
+    /// Export the whole universe as a pattern in the run length encoded
+    /// (RLE) format, with a `x = width, y = height, rule = ...` header.
+    ///
+    /// Rows are made of runs of `b` (dead) and `o` (alive) cells, prefixed
+    /// with their length unless it is 1, and end with `$`; dead cells at the
+    /// end of a row and empty rows at the end of the pattern are left out, as
+    /// usual. Lines are wrapped to at most `RLE_LINE_LENGTH` characters.
+    pub fn to_rle(&self) -> String {
+        let mut rle = format!("x = {}, y = {}, rule = {}\n", self.width, self.height, self.rule());
+        let mut line = String::new();
+        let mut push = |token: String| {
+            if line.len() + token.len() > RLE_LINE_LENGTH {
+                rle.push_str(&line);
+                rle.push('\n');
+                line.clear();
+            }
+            line.push_str(&token);
+        };
+        let run = |length: u32, tag: char| {
+            if length == 1 {
+                tag.to_string()
+            } else {
+                format!("{}{}", length, tag)
+            }
+        };
+
+        let mut row_ends = 0;
+        for row in 0..self.height {
+            let mut col = 0;
+            while col < self.width {
+                let alive = self.cells[self.get_index(row, col)];
+                let start = col;
+                while col < self.width && self.cells[self.get_index(row, col)] == alive {
+                    col += 1;
+                }
+                if !alive && col == self.width {
+                    break;
+                }
+
+                if row_ends > 0 {
+                    push(run(row_ends, '$'));
+                    row_ends = 0;
+                }
+                push(run(col - start, if alive { 'o' } else { 'b' }));
+            }
+            row_ends += 1;
+        }
+        push("!".to_string());
+
+        rle.push_str(&line);
+        rle.push('\n');
+        rle
+    }
+
+    /// Like `to_rle`, but only for the smallest rectangle holding every live
+    /// cell, see `live_bounding_box`, which is how patterns are usually
+    /// shared. An empty universe gives an empty `x = 0, y = 0` pattern.
+    pub fn to_rle_trimmed(&self) -> String {
+        let (row, col, height, width) = self.live_bounding_box().unwrap_or((0, 0, 0, 0));
+        self.subgrid(row, col, height, width).to_rle()
+    }
+
+    /// Bring to life the live cells of a pattern in the RLE format, as
+    /// written by `to_rle`, with the top-left cell of the pattern at
+    /// `(row, col)`, wrapping around the edges if needed. Cells the pattern
+    /// leaves dead are not touched, and neither is the rule, even if the
+    /// header names one.
+    pub fn insert_rle(&mut self, rle: &str, row: u32, col: u32) -> Result<(), Error> {
+        let pattern = parse_rle(rle)?;
+        if self.width == 0 || self.height == 0 {
+            return Ok(());
+        }
+
+        let cells: Vec<(u32, u32)> = pattern
+            .cells
+            .iter()
+            .map(|&(r, c)| {
+                let r = (row as u64 + r as u64) % self.height as u64;
+                let c = (col as u64 + c as u64) % self.width as u64;
+                (r as u32, c as u32)
+            })
+            .collect();
+        self.set_cells(&cells);
+        Ok(())
+    }
+
+    /// Replace the contents of the universe with a pattern in the RLE
+    /// format, placed in the middle of the board, and switch to the rule of
+    /// its header if it names one.
+    ///
+    /// The universe is not resized: a pattern larger than the board is
+    /// rejected with `Error::TooSmall`, leaving the universe as it was, since
+    /// wrapping it around the edges would garble it.
+    pub fn load_rle_centered(&mut self, rle: &str) -> Result<(), Error> {
+        let pattern = parse_rle(rle)?;
+        if pattern.width > self.width || pattern.height > self.height {
+            return Err(Error::TooSmall {
+                width: self.width,
+                height: self.height,
+                min_width: pattern.width,
+                min_height: pattern.height,
+            });
+        }
+        if let Some(rule) = &pattern.rule {
+            self.set_rule(rule)?;
+        }
+
+        let row = (self.height - pattern.height) / 2;
+        let col = (self.width - pattern.width) / 2;
+        self.clear();
+        self.insert_rle(rle, row, col)
+    }
+}
+
+// A pattern read from RLE text by `parse_rle`.
+struct RlePattern {
+    width: u32,
+    height: u32,
+    rule: Option<String>,
+    cells: Vec<(u32, u32)>,
+}
+
+// Read a pattern in the RLE format: `#` comment lines, an optional
+// `x = ..., y = ..., rule = ...` header, then runs of `b` (dead) and `o`
+// (alive) cells, with `$` ending rows and `!` ending the pattern. The
+// dimensions are those of the header, grown if the cells don't fit in them.
+// Patterns beyond `MAX_RLE_SIZE` or `MAX_RLE_CELLS` are rejected.
+fn parse_rle(rle: &str) -> Result<RlePattern, Error> {
+    let mut pattern = RlePattern { width: 0, height: 0, rule: None, cells: Vec::new() };
+    let (mut row, mut col) = (0u32, 0u32);
+    let mut count: Option<u32> = None;
+
+    'lines: for line in rle.lines().map(str::trim) {
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        if line.starts_with('x') {
+            for field in line.split(',') {
+                let mut parts = field.splitn(2, '=').map(str::trim);
+                let (key, value) = match (parts.next(), parts.next()) {
+                    (Some(key), Some(value)) => (key, value),
+                    _ => return Err(Error::InvalidData("malformed RLE header")),
+                };
+                let dimension = || match value.parse() {
+                    Ok(size) if size <= MAX_RLE_SIZE => Ok(size),
+                    Ok(_) => Err(Error::InvalidData("RLE pattern too large")),
+                    Err(_) => Err(Error::InvalidData("malformed RLE header")),
+                };
+                match key {
+                    "x" => pattern.width = dimension()?,
+                    "y" => pattern.height = dimension()?,
+                    "rule" => pattern.rule = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+            continue;
+        }
+
+        for symbol in line.chars() {
+            if let Some(digit) = symbol.to_digit(10) {
+                let run = count.unwrap_or(0).checked_mul(10).and_then(|n| n.checked_add(digit));
+                count = Some(run.ok_or(Error::InvalidData("RLE run too long"))?);
+                continue;
+            }
+            let run = count.take().unwrap_or(1);
+            match symbol {
+                'b' | '.' => col = col.saturating_add(run),
+                'o' | '*' => {
+                    let end = col.saturating_add(run);
+                    if row >= MAX_RLE_SIZE || end > MAX_RLE_SIZE || pattern.cells.len() + run as usize > MAX_RLE_CELLS {
+                        return Err(Error::InvalidData("RLE pattern too large"));
+                    }
+                    pattern.cells.extend((col..end).map(|c| (row, c)));
+                    col = end;
+                    pattern.width = pattern.width.max(col);
+                    pattern.height = pattern.height.max(row.saturating_add(1));
+                }
+                '$' => {
+                    row = row.saturating_add(run);
+                    col = 0;
+                }
+                '!' => break 'lines,
+                symbol if symbol.is_whitespace() => {}
+                _ => return Err(Error::InvalidData("unexpected character in RLE pattern")),
+            }
+        }
+    }
+
+    Ok(pattern)
+}
+
+#[wasm_bindgen]
+impl Universe {
+    /// Serialize the universe's dimensions and cells into a compact blob,
+    /// which `from_bytes` turns back into a universe.
+    ///
+    /// The layout is a magic byte, the width and the height as little-endian
+    /// `u32`s, then the cells packed 32 per little-endian `u32` word in
+    /// row-major order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.bytes_header(BYTES_MAGIC);
+        for word in self.logical_words() {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Build a universe from a blob produced by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Universe, Error> {
+        let (width, height, mut body) = parse_bytes_header(bytes, BYTES_MAGIC)?;
+
+        let mut words = Vec::new();
+        while !body.is_empty() {
+            words.push(read_u32(&mut body)?);
+        }
+        Universe::from_words(width, height, &words)
+    }
+
+    /// Whether `to_bytes` and `to_bytes_compressed` both give back the same
+    /// board once read by `from_bytes` and `from_bytes_compressed`, i.e.
+    /// the same dimensions and cells, which is all they store, as a self
+    /// test of saving and loading. This copies the board several times, so
+    /// it is not meant for every tick.
+    pub fn verify_roundtrip(&self) -> bool {
+        let plain = Universe::from_bytes(&self.to_bytes());
+        let compressed = Universe::from_bytes_compressed(&self.to_bytes_compressed());
+        plain.is_ok_and(|universe| universe.same_cells(self))
+            && compressed.is_ok_and(|universe| universe.same_cells(self))
+    }
+
+    /// `to_bytes` encoded in the URL-safe base64 alphabet, with `-` and `_`
+    /// for the last two digits and without `=` padding, so that a board can
+    /// be shared in a URL query string as is.
+    pub fn to_base64(&self) -> String {
+        encode_base64(&self.to_bytes(), BASE64_URL_ALPHABET, false)
+    }
+
+    /// Build a universe from the text produced by `to_base64`.
+    pub fn from_base64(text: &str) -> Result<Universe, Error> {
+        let text = text.trim().as_bytes();
+        if text.len() % 4 == 1 {
+            return Err(Error::InvalidData("truncated base64 text"));
+        }
+
+        let mut bytes = Vec::with_capacity(text.len() / 4 * 3 + 2);
+        for chunk in text.chunks(4) {
+            let mut group = 0u32;
+            for &digit in chunk {
+                let value = BASE64_URL_ALPHABET
+                    .iter()
+                    .position(|&d| d == digit)
+                    .ok_or(Error::InvalidData("invalid character in base64 text"))?;
+                group = group << 6 | value as u32;
+            }
+            group <<= 6 * (4 - chunk.len());
+            for byte in 0..chunk.len() - 1 {
+                bytes.push((group >> (16 - 8 * byte)) as u8);
+            }
+        }
+        Universe::from_bytes(&bytes)
+    }
+
+    /// Like `to_bytes`, but with runs of identical words run-length encoded,
+    /// which `from_bytes_compressed` turns back into a universe.
+    ///
+    /// After the magic byte and dimensions, each run is stored as its length
+    /// as a LEB128 varint followed by the repeated word. Sparse boards, which
+    /// are mostly made of zero words, shrink to a handful of bytes, but dense
+    /// and noisy boards can take up to a quarter more space than with
+    /// `to_bytes`, which should be preferred for them.
+    pub fn to_bytes_compressed(&self) -> Vec<u8> {
+        let mut bytes = self.bytes_header(COMPRESSED_BYTES_MAGIC);
+
+        let mut words = self.logical_words().peekable();
+        while let Some(word) = words.next() {
+            let mut run = 1u32;
+            while words.peek() == Some(&word) {
+                words.next();
+                run += 1;
+            }
+            write_varint(&mut bytes, run);
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Build a universe from a blob produced by `to_bytes_compressed`.
+    pub fn from_bytes_compressed(bytes: &[u8]) -> Result<Universe, Error> {
+        let (width, height, mut body) = parse_bytes_header(bytes, COMPRESSED_BYTES_MAGIC)?;
+
+        let max_words = ((width as u64 * height as u64).div_ceil(32)) as usize;
+        let mut words = Vec::new();
+        while !body.is_empty() {
+            let run = read_varint(&mut body)? as usize;
+            let word = read_u32(&mut body)?;
+            if run > max_words - words.len() {
+                return Err(Error::InvalidData("too many cells for the universe's dimensions"));
+            }
+            words.reserve(run);
+            words.extend(std::iter::repeat_n(word, run));
+        }
+        Universe::from_words(width, height, &words)
+    }
+
+    /// Build a `width` by `height` universe from a grayscale image, given as
+    /// one byte per pixel in row-major order, in which every pixel brighter
+    /// than `threshold` is a live cell.
+    ///
+    /// The image must already be decoded, and have exactly one pixel per
+    /// cell.
+    pub fn from_image_bytes(data: &[u8], width: u32, height: u32, threshold: u8) -> Result<Universe, Error> {
+        let size = width as u64 * height as u64;
+        if data.len() as u64 != size {
+            return Err(Error::WrongLength {
+                what: "pixels",
+                expected: size,
+                found: data.len() as u64,
+            });
+        }
+
+        let mut cells = FixedBitSet::with_capacity(data.len());
+        for (i, pixel) in data.iter().enumerate() {
+            cells.set(i, *pixel > threshold);
+        }
+        Ok(Universe::from_bitset(width, height, cells))
+    }
+
+    /// Encode the universe as a PNG image with one pixel per cell, black
+    /// for live cells and white for dead ones. A universe without any cells
+    /// gives a single white pixel, since an image can't be empty.
+    ///
+    /// The image is 8-bit grayscale, and not compressed, so it takes a byte
+    /// per cell.
+    pub fn to_png(&self) -> Vec<u8> {
+        let (width, height) = (self.width.max(1), self.height.max(1));
+        let mut pixels = Vec::with_capacity(((width + 1) * height) as usize);
+        for row in 0..height {
+            // Each row starts with its filter type, none.
+            pixels.push(0);
+            for col in 0..width {
+                let alive = row < self.height && col < self.width && self.cells[self.get_index(row, col)];
+                pixels.push(if alive { 0x00 } else { 0xff });
+            }
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&width.to_be_bytes());
+        header.extend_from_slice(&height.to_be_bytes());
+        // Bit depth 8, grayscale, and the only compression, filter and
+        // interlace methods, or lack thereof.
+        header.extend_from_slice(&[8, 0, 0, 0, 0]);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        write_png_chunk(&mut png, b"IHDR", &header);
+        write_png_chunk(&mut png, b"IDAT", &zlib_stored(&pixels));
+        write_png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    /// A `data:image/png;base64,...` URL of a picture of the board, like
+    /// `to_png`, at most `max_dim` pixels wide and high, e.g. for the `src`
+    /// of an `<img>` previewing a pattern.
+    ///
+    /// Larger boards are scaled down by a whole factor, as by `downsample`,
+    /// with a pixel black when any cell it stands for is alive, so that
+    /// small patterns don't vanish. `max_dim` is capped at
+    /// `MAX_THUMBNAIL_SIZE`, to keep the URL reasonably short, and a
+    /// `max_dim` of 0 is treated as 1.
+    pub fn thumbnail_data_url(&self, max_dim: u32) -> String {
+        let factor = self.fit_factor(max_dim.clamp(1, MAX_THUMBNAIL_SIZE));
+        let thumbnail = self.downsampled(factor);
+        format!("data:image/png;base64,{}", encode_base64(&thumbnail.to_png(), BASE64_ALPHABET, true))
+    }
+
+    /// Advance the universe by `generations` ticks, and encode the board
+    /// after each of them as a frame of a looping GIF animation, shown for
+    /// `delay_ms` milliseconds each, rounded down to the hundredths of a
+    /// second that GIF counts in. Each cell is a `cell_size` by `cell_size`
+    /// square, black if alive and white if dead. With no generations, the
+    /// animation is a single frame of the board as it is.
+    ///
+    /// To bound memory use, `cell_size` is reduced as needed to keep the
+    /// image within `MAX_GIF_SIZE` pixels wide and high, down to 1. Boards
+    /// larger than that are scaled down by a whole factor, with a pixel
+    /// black when any cell it stands for is alive, like in
+    /// `thumbnail_data_url`. The number of generations is then reduced to
+    /// keep all frames within `MAX_GIF_PIXELS` pixels, the board being only
+    /// advanced by that many ticks.
+    ///
+    /// The frames are LZW compressed, as GIF requires, so a sparse board
+    /// takes far less than a bit per pixel.
+    pub fn to_gif(&mut self, generations: u32, cell_size: u32, delay_ms: u16) -> Vec<u8> {
+        let factor = self.fit_factor(MAX_GIF_SIZE);
+        let largest = self.width.max(self.height).max(1);
+        let cell_size = if factor > 1 { 1 } else { cell_size.min(MAX_GIF_SIZE / largest).max(1) };
+        let width = (self.width.div_ceil(factor) * cell_size).max(1);
+        let height = (self.height.div_ceil(factor) * cell_size).max(1);
+        let generations = generations.min((MAX_GIF_PIXELS / (width as u64 * height as u64)) as u32);
+
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend_from_slice(&(width as u16).to_le_bytes());
+        gif.extend_from_slice(&(height as u16).to_le_bytes());
+        // A global colour table of 2 colours, white then black, background
+        // white, and square pixels.
+        gif.extend_from_slice(&[0x80, 0, 0]);
+        gif.extend_from_slice(&[0xff, 0xff, 0xff, 0x00, 0x00, 0x00]);
+        // Loop forever.
+        gif.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+        let mut pixels = vec![0; (width * height) as usize];
+        for _ in 0..generations.max(1) {
+            if generations > 0 {
+                self.tick();
+            }
+
+            pixels.iter_mut().for_each(|pixel| *pixel = 0);
+            let live = if factor > 1 { self.downsampled(factor).live_cells() } else { self.live_cells() };
+            for (row, col) in live {
+                let (y, x) = (row * cell_size, col * cell_size);
+                for dy in 0..cell_size {
+                    let start = ((y + dy) * width + x) as usize;
+                    pixels[start..start + cell_size as usize].iter_mut().for_each(|pixel| *pixel = 1);
+                }
+            }
+
+            // Graphic control extension with the delay, then the image
+            // descriptor of a frame covering the whole animation.
+            gif.extend_from_slice(&[0x21, 0xf9, 0x04, 0x00]);
+            gif.extend_from_slice(&(delay_ms / 10).to_le_bytes());
+            gif.extend_from_slice(&[0x00, 0x00, 0x2c, 0, 0, 0, 0]);
+            gif.extend_from_slice(&(width as u16).to_le_bytes());
+            gif.extend_from_slice(&(height as u16).to_le_bytes());
+            gif.push(0);
+
+            // The smallest LZW code size GIF allows, in sub-blocks of at
+            // most 255 bytes, and an empty one to end them.
+            gif.push(2);
+            for block in lzw_compress(&pixels).chunks(255) {
+                gif.push(block.len() as u8);
+                gif.extend_from_slice(block);
+            }
+            gif.push(0);
+        }
+        gif.push(0x3b);
+        gif
+    }
+
+    /// Encode the board as a NumPy `.npy` file, version 1.0, holding a
+    /// `height` by `width` array of `uint8`, 1 for live cells and 0 for dead
+    /// ones, row by row, e.g. for `numpy.load` to read it back in Python.
+    pub fn to_npy(&self) -> Vec<u8> {
+        let mut header = format!(
+            "{{'descr': '|u1', 'fortran_order': False, 'shape': ({}, {}), }}",
+            self.height, self.width
+        );
+        // The header ends with a newline, padded with spaces before it so
+        // that the data starts at a multiple of 64 bytes, after the 6 bytes
+        // of the magic string, 2 of the version and 2 of the header length.
+        let padding = 63 - (10 + header.len()) % 64;
+        header.extend(std::iter::repeat_n(' ', padding));
+        header.push('\n');
+
+        let mut npy = b"\x93NUMPY\x01\x00".to_vec();
+        npy.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        npy.extend_from_slice(header.as_bytes());
+        npy.reserve((self.width * self.height) as usize);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                npy.push(self.cells[self.get_index(row, col)] as u8);
+            }
+        }
+        npy
+    }
+}
+
+impl Universe {
+    // Smallest whole factor by which to scale the board down, as by
+    // `downsampled`, for it to be at most `max_dim` cells wide and high.
+    fn fit_factor(&self, max_dim: u32) -> u32 {
+        self.width.max(self.height).div_ceil(max_dim.max(1)).max(1)
+    }
+
+    // The board scaled down by `factor` as by `downsample`, with a cell
+    // alive when any cell of its block is.
+    fn downsampled(&self, factor: u32) -> Universe {
+        let factor = factor.max(1);
+        let (width, height) = (self.width.div_ceil(factor), self.height.div_ceil(factor));
+        let mut cells = FixedBitSet::with_capacity((width * height) as usize);
+        for (idx, &count) in self.downsample(factor).iter().enumerate() {
+            cells.set(idx, count > 0);
+        }
+        Universe::from_bitset(width, height, cells)
+    }
+
+    fn bytes_header(&self, magic: u8) -> Vec<u8> {
+        let mut bytes = vec![magic];
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes
+    }
+
+    // Build a universe from its cells packed in words, as in the buffer
+    // behind `cells`.
+    fn from_words(width: u32, height: u32, words: &[u32]) -> Result<Universe, Error> {
+        let size = width as u64 * height as u64;
+        if words.len() as u64 != size.div_ceil(32) {
+            return Err(Error::InvalidData("wrong number of cells for the universe's dimensions"));
+        }
+
+        let size = size as usize;
+        let cells = FixedBitSet::with_capacity_and_blocks(size, words.iter().cloned());
+        Ok(Universe::from_bitset(width, height, cells))
+    }
+}
+
+// Encode bytes in base64 with the digits of `alphabet`, padded with `=` to
+// a multiple of 4 digits if `pad`.
+fn encode_base64(bytes: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().fold(0u32, |group, &byte| group << 8 | byte as u32) << (8 * (3 - chunk.len()));
+        for digit in 0..=chunk.len() {
+            let value = (group >> (18 - 6 * digit)) & 0x3f;
+            text.push(alphabet[value as usize] as char);
+        }
+        if pad {
+            for _ in chunk.len()..3 {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+// Append a PNG chunk: the length of its data, its type, the data, and the
+// CRC-32 of the type and data.
+fn write_png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    let crc_start = png.len() + 4;
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[crc_start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+// The bytes of a zlib stream holding `data` in uncompressed deflate blocks,
+// which every PNG decoder can read without implementing compression here.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xffff;
+
+    // Deflate with a 32 KiB window, and no preset dictionary.
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        stream.push(last as u8);
+        stream.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        stream.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+
+    // Adler-32 checksum of the uncompressed data.
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    stream.extend_from_slice(&(b << 16 | a).to_be_bytes());
+    stream
+}
+
+// The GIF LZW data of pixels of colour 0 or 1, with a code size of 2.
+//
+// Each code stands for a string of pixels in a table that starts with the
+// single pixels, the clear code and the end code, and grows by one string
+// for each code written: the string just written followed by the pixel after
+// it. Codes start 3 bits wide and widen as the table outgrows them, up to
+// the 12 bits that GIF allows, after which the table is cleared.
+fn lzw_compress(pixels: &[u8]) -> Vec<u8> {
+    const CLEAR: u16 = 4;
+    const END: u16 = 5;
+    const MAX_CODES: usize = 1 << 12;
+
+    let mut codes = CodeWriter { data: Vec::new(), bits: 0, count: 0, size: 3 };
+    codes.write(CLEAR);
+
+    // The codes of the strings extending each string by a pixel of colour 0
+    // or 1, or 0 if not in the table yet, which is no string's code.
+    let mut table: Vec<[u16; 2]> = vec![[0; 2]; END as usize + 1];
+    let mut pixels = pixels.iter().map(|&pixel| pixel.min(1) as usize);
+    let mut string = match pixels.next() {
+        Some(pixel) => pixel as u16,
+        None => {
+            codes.write(END);
+            return codes.finish();
+        }
+    };
+    for pixel in pixels {
+        let next = table[string as usize][pixel];
+        if next != 0 {
+            string = next;
+            continue;
+        }
+
+        codes.write(string);
+        if table.len() < MAX_CODES {
+            table[string as usize][pixel] = table.len() as u16;
+            table.push([0; 2]);
+            if table.len() > 1 << codes.size && codes.size < 12 {
+                codes.size += 1;
+            }
+        } else {
+            codes.write(CLEAR);
+            table.truncate(END as usize + 1);
+            table.iter_mut().for_each(|next| *next = [0; 2]);
+            codes.size = 3;
+        }
+        string = pixel as u16;
+    }
+    codes.write(string);
+    codes.write(END);
+    codes.finish()
+}
+
+// Packs codes of `size` bits each into bytes, least significant bit first,
+// as GIF's LZW data is.
+struct CodeWriter {
+    data: Vec<u8>,
+    bits: u32,
+    count: u32,
+    size: u32,
+}
+
+impl CodeWriter {
+    fn write(&mut self, code: u16) {
+        self.bits |= (code as u32) << self.count;
+        self.count += self.size;
+        while self.count >= 8 {
+            self.data.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.data.push(self.bits as u8);
+        }
+        self.data
+    }
+}
+
+// CRC-32 as used by PNG, bit by bit, which is fast enough for thumbnails.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { 0xedb8_8320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+// Check the magic byte of a serialized universe and read its dimensions,
+// returning them along with the rest of the blob. Universes of more than
+// `MAX_BYTES_CELLS` cells are rejected.
+fn parse_bytes_header(bytes: &[u8], magic: u8) -> Result<(u32, u32, &[u8]), Error> {
+    match bytes.split_first() {
+        Some((&first, mut rest)) if first == magic => {
+            let width = read_u32(&mut rest)?;
+            let height = read_u32(&mut rest)?;
+            if width as u64 * height as u64 > MAX_BYTES_CELLS {
+                return Err(Error::InvalidData("universe dimensions are too large"));
+            }
+            Ok((width, height, rest))
+        }
+        _ => Err(Error::InvalidData("not a serialized universe in this format")),
+    }
+}
+
+fn read_u32(bytes: &mut &[u8]) -> Result<u32, Error> {
+    if bytes.len() < 4 {
+        return Err(Error::InvalidData("truncated serialized universe"));
+    }
+    let (word, rest) = bytes.split_at(4);
+    *bytes = rest;
+    Ok(u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u32, Error> {
+    let mut value = 0u32;
+    for shift in (0..35).step_by(7) {
+        let (&byte, rest) = bytes
+            .split_first()
+            .ok_or(Error::InvalidData("truncated serialized universe"))?;
+        *bytes = rest;
+        value |= ((byte & 0x7f) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(Error::InvalidData("invalid run length in serialized universe"))
+}
+
+#[test]
+fn test_from_life105_blocks() {
+    let text = "#Life 1.05\n\
+                #D Two blocks of a glider\n\
+                #N\n\
+                #P -1 -1\n\
+                .*.\n\
+                ..*\n\
+                #P -1 1\n\
+                ***\n";
+    let universe = Universe::from_life105(text, 8, 8);
+    assert_eq!(
+        universe.live_cells(),
+        vec![(3, 4), (4, 5), (5, 3), (5, 4), (5, 5)]
+    );
+}
+
+#[test]
+fn test_to_rle() {
+    let mut universe = Universe::from_cells(8, 6, &[]);
+    universe.insert_pattern(crate::Pattern::Glider, 1, 3);
+    assert_eq!(universe.to_rle(), "x = 8, y = 6, rule = B3/S23\n$4bo$5bo$3b3o!\n");
+    assert_eq!(universe.to_rle_trimmed(), "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n");
+
+    let empty = Universe::from_cells(8, 6, &[]);
+    assert_eq!(empty.to_rle_trimmed(), "x = 0, y = 0, rule = B3/S23\n!\n");
+
+    // A dense board wraps its lines.
+    let checkerboard: Vec<bool> = (0..100 * 2).map(|i| i % 2 == 0).collect();
+    let rle = Universe::from_vec_bool(100, 2, &checkerboard).to_rle();
+    assert!(rle.lines().all(|line| line.len() <= RLE_LINE_LENGTH));
+    assert_eq!(rle.lines().skip(1).collect::<String>().matches('o').count(), 100);
+}
+
+#[test]
+fn test_bytes_round_trip() {
+    let universe = Universe::new_seeded(37, 13, 5);
+
+    let bytes = universe.to_bytes();
+    assert_eq!(bytes.len(), 9 + 4 * 16);
+    let decoded = Universe::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.to_vec_bool(), universe.to_vec_bool());
+
+    let compressed = universe.to_bytes_compressed();
+    let decoded = Universe::from_bytes_compressed(&compressed).unwrap();
+    assert_eq!(decoded.to_vec_bool(), universe.to_vec_bool());
+}
+
+#[test]
+fn test_sparse_board_compresses() {
+    let mut universe = Universe::from_cells(512, 512, &[]);
+    universe.insert_pattern(crate::Pattern::Glider, 100, 100);
+
+    let compressed = universe.to_bytes_compressed();
+    assert!(compressed.len() < 64, "{} bytes", compressed.len());
+    assert!(universe.to_bytes().len() > 32 * 1024);
+
+    let decoded = Universe::from_bytes_compressed(&compressed).unwrap();
+    assert_eq!(decoded.live_cells(), universe.live_cells());
+}
+
+#[test]
+fn test_from_bytes_compressed_rejects_huge_universes() {
+    // A 65536x65535 header and a single run of 2^32 - 1 words.
+    let mut bytes = vec![COMPRESSED_BYTES_MAGIC];
+    bytes.extend_from_slice(&65536u32.to_le_bytes());
+    bytes.extend_from_slice(&65535u32.to_le_bytes());
+    bytes.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0x0f, 0, 0, 0, 0]);
+    assert!(matches!(Universe::from_bytes_compressed(&bytes), Err(Error::InvalidData(_))));
+
+    // A run longer than the board, however small.
+    let mut bytes = Universe::from_cells(8, 8, &[]).bytes_header(COMPRESSED_BYTES_MAGIC);
+    bytes.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0x0f, 0, 0, 0, 0]);
+    assert!(matches!(Universe::from_bytes_compressed(&bytes), Err(Error::InvalidData(_))));
+}
+
+#[test]
+fn test_from_image_bytes() {
+    let pixels = [0, 200, 128, 129, 255, 12];
+    let universe = Universe::from_image_bytes(&pixels, 3, 2, 128).unwrap();
+    assert_eq!(universe.live_cells(), vec![(0, 1), (1, 0), (1, 1)]);
+}
+
+#[test]
+fn test_load_rle_centered() {
+    let glider = "#C A glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+    let mut universe = Universe::from_cells(9, 9, &[(0, 0)]);
+    universe.load_rle_centered(glider).unwrap();
+    let expected = Universe::from_cells(9, 9, &[(3, 4), (4, 5), (5, 3), (5, 4), (5, 5)]);
+    assert!(universe.same_cells(&expected));
+
+    let mut small = Universe::from_cells(2, 2, &[(0, 0)]);
+    assert!(matches!(small.load_rle_centered(glider), Err(Error::TooSmall { .. })));
+    assert_eq!(small.population(), 1);
+    assert!(matches!(small.load_rle_centered("x = 1, y = 1\nq!"), Err(Error::InvalidData(_))));
+
+    // Patterns too large to hold are rejected before anything is allocated.
+    let too_large = Err(Error::InvalidData("RLE pattern too large"));
+    assert_eq!(small.insert_rle("4294967295$o!", 0, 0), too_large);
+    assert_eq!(small.insert_rle("4000000000o!", 0, 0), too_large);
+    assert_eq!(small.load_rle_centered("x = 100000, y = 1\no!"), too_large);
+    assert_eq!(small.population(), 1);
+
+    // What `to_rle` writes reads back into the same cells.
+    let mut copy = Universe::from_cells(9, 9, &[]);
+    copy.load_rle_centered(&expected.to_rle()).unwrap();
+    assert!(copy.same_cells(&expected));
+}
+
+#[test]
+fn test_base64_round_trip() {
+    let universe = Universe::from_cells(7, 5, &[(0, 1), (2, 6), (4, 0), (4, 4)]);
+    let text = universe.to_base64();
+    assert!(text.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'));
+    assert!(Universe::from_base64(&text).unwrap().same_cells(&universe));
+
+    // The blob above is 17 bytes long, ending with 2 of a group of 3, which
+    // take 3 digits. These are 13 and 21 bytes long.
+    for size in &[(1, 1), (9, 9)] {
+        let universe = Universe::from_cells(size.0, size.1, &[(0, 0)]);
+        assert!(Universe::from_base64(&universe.to_base64()).unwrap().same_cells(&universe));
+    }
+
+    assert!(matches!(Universe::from_base64("TA=="), Err(Error::InvalidData(_))));
+    assert!(matches!(Universe::from_base64("TAAAA"), Err(Error::InvalidData(_))));
+}
+
+#[test]
+fn test_to_png() {
+    assert_eq!(crc32(b"IEND"), 0xae42_6082);
+
+    let universe = Universe::from_cells(3, 2, &[(0, 1), (1, 2)]);
+    let png = universe.to_png();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(&png[16..24], &[0, 0, 0, 3, 0, 0, 0, 2]);
+    assert_eq!(&png[png.len() - 12..], &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]);
+
+    // The IDAT chunk holds a single stored block with the filtered rows.
+    let pixels = [0, 0xff, 0x00, 0xff, 0, 0xff, 0xff, 0x00];
+    let idat = 33;
+    assert_eq!(&png[idat + 4..idat + 8], b"IDAT");
+    assert_eq!(&png[idat + 15..idat + 15 + pixels.len()], &pixels);
+}
+
+#[test]
+fn test_thumbnail_data_url() {
+    let mut universe = Universe::from_cells(100, 40, &[]);
+    universe.insert_pattern(crate::Pattern::Glider, 20, 50);
+    let url = universe.thumbnail_data_url(10);
+    let base64 = url.strip_prefix("data:image/png;base64,").unwrap();
+    assert_eq!(base64.len() % 4, 0);
+    assert!(base64.bytes().all(|b| b.is_ascii_alphanumeric() || b"+/=".contains(&b)));
+
+    // Scaled down by 10, to 10x4 pixels, with the glider in a single one.
+    let thumbnail = Universe::from_cells(10, 4, &[(2, 5)]);
+    assert_eq!(base64, encode_base64(&thumbnail.to_png(), BASE64_ALPHABET, true));
+
+    assert_eq!(encode_base64(b"ab", BASE64_ALPHABET, true), "YWI=");
+    assert_eq!(encode_base64(b"a", BASE64_ALPHABET, true), "YQ==");
+}
+
+#[test]
+fn test_verify_roundtrip() {
+    assert!(Universe::new_hardcoded(33, 17).verify_roundtrip());
+    assert!(Universe::from_cells(0, 0, &[]).verify_roundtrip());
+    let mut universe = Universe::from_cells(40, 40, &[]);
+    universe.insert_pattern(crate::Pattern::GosperGliderGun, 2, 2);
+    assert!(universe.verify_roundtrip());
+}
+
+#[test]
+fn test_to_gif() {
+    let mut universe = Universe::from_cells(2, 1, &[(0, 0)]);
+    universe.set_rule("B/S012345678").unwrap();
+    let gif = universe.to_gif(1, 1, 250);
+    assert_eq!(universe.generation(), 1);
+    assert_eq!(&gif[..13], b"GIF89a\x02\x00\x01\x00\x80\x00\x00");
+    assert_eq!(gif.last(), Some(&0x3b));
+
+    // A delay of 25 hundredths of a second, then the codes clear, 1, 0 and
+    // end, 3 bits each.
+    let frame = &gif[gif.len() - 24..];
+    assert_eq!(&frame[..8], &[0x21, 0xf9, 0x04, 0x00, 25, 0, 0, 0]);
+    assert_eq!(&frame[18..], &[2, 2, 0x0c, 0x0a, 0, 0x3b]);
+
+    // Each cell is drawn as a square, and the frames stop at the limit.
+    let mut universe = Universe::from_cells(4, 2, &[]);
+    let gif = universe.to_gif(3, 10, 100);
+    assert_eq!(&gif[6..10], &[40, 0, 20, 0]);
+    assert_eq!(universe.generation(), 3);
+    let mut universe = Universe::from_cells(1024, 1024, &[]);
+    universe.to_gif(1000, 4, 100);
+    assert_eq!(universe.generation(), 64);
+
+    // Boards wider than `MAX_GIF_SIZE` are scaled down to fit, and there is
+    // always a frame, even with no generations.
+    let mut universe = Universe::from_cells(70000, 1, &[(0, 69999)]);
+    let gif = universe.to_gif(0, 4, 100);
+    // 70000 columns by a factor of 69 give 1015 pixels.
+    assert_eq!(&gif[6..10], &[0xf7, 0x03, 1, 0]);
+    assert_eq!(universe.generation(), 0);
+    assert_eq!(gif.windows(3).filter(|window| window == &[0x21, 0xf9, 0x04]).count(), 1);
+
+    // An empty frame of 65536 pixels compresses into a few hundred codes.
+    let gif = Universe::from_cells(256, 256, &[]).to_gif(1, 1, 100);
+    assert!(gif.len() < 1024, "{} bytes", gif.len());
+}
+
+#[test]
+fn test_to_npy() {
+    let universe = Universe::from_cells(3, 2, &[(0, 1), (1, 2)]);
+    let npy = universe.to_npy();
+    assert_eq!(&npy[..8], b"\x93NUMPY\x01\x00");
+    assert_eq!(u16::from_le_bytes([npy[8], npy[9]]), 118);
+
+    let header = std::str::from_utf8(&npy[10..128]).unwrap();
+    assert!(header.starts_with("{'descr': '|u1', 'fortran_order': False, 'shape': (2, 3), }"));
+    assert!(header.ends_with(" \n"));
+    assert_eq!(&npy[128..], &[0, 1, 0, 0, 0, 1]);
+}
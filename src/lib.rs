@@ -1,3 +1,9 @@
+mod ant;
+mod error;
+mod formats;
+mod patterns;
+mod rules;
+mod script;
 mod utils;
 
 use wasm_bindgen::prelude::*;
@@ -6,14 +12,29 @@ extern crate js_sys;
 extern crate fixedbitset;
 extern  crate web_sys;
 
-// A macro to provide `println!(..)`-style syntax for `console.log` logging.
+// A macro to provide `println!(..)`-style syntax for `console.log` logging,
+// taking the level of the message first. Messages above the level set with
+// `set_log_level` are dropped without being formatted.
+// `console.log` only exists when running as wasm, so messages go to stderr
+// elsewhere, e.g. in native unit tests.
 macro_rules! log {
-    ( $( $t:tt )* ) => {
-        web_sys::console::log_1(&format!( $( $t )* ).into());
+    ( $level:expr, $( $t:tt )* ) => {
+        if utils::log_enabled($level) {
+            #[cfg(target_arch = "wasm32")]
+            web_sys::console::log_1(&format!( $( $t )* ).into());
+            #[cfg(not(target_arch = "wasm32"))]
+            eprintln!( $( $t )* );
+        }
     }
 }
 
 use fixedbitset::FixedBitSet;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::TryInto;
+
+pub use ant::Heading;
+pub use error::Error;
+pub use patterns::Pattern;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -21,14 +42,350 @@ use fixedbitset::FixedBitSet;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+// 3x3 neighbourhood of the glider stamped by `new_glider_at` and
+// `add_glider_at`, in the format taken by `set_pattern_at`.
+const GLIDER: [u8; 9] = [
+    0, 1, 1,
+    1, 0, 1,
+    0, 0, 1,
+];
+
+// Rule masks for Conway's game of life, B3/S23.
+const CONWAY_BIRTH: u32 = 1 << 3;
+const CONWAY_SURVIVAL: u32 = 1 << 2 | 1 << 3;
+
+// Population of `Universe::new_gun_and_eater` at every multiple of 30
+// generations, once the eater has eaten its first glider.
+pub const GUN_AND_EATER_POPULATION: u32 = 49;
+
+// Default symbols for live and dead cells in `Universe::render_emoji`.
+const DEFAULT_EMOJI_ALIVE: &str = "⬛";
+const DEFAULT_EMOJI_DEAD: &str = "⬜";
+
+// Upper bound on the size of the buffer returned by `Universe::trajectory`.
+const MAX_TRAJECTORY_BYTES: usize = 64 * 1024 * 1024;
+
+// Upper bound on the number of states remembered by
+// `Universe::tick_until_repeat`.
+const MAX_REPEAT_STATES: usize = 1 << 20;
+
+// Version of the layout of the cells behind `Universe::cells`, reported by
+// `Universe::bit_layout`, to be bumped whenever it changes.
+pub const CELLS_LAYOUT_VERSION: u32 = 1;
+
+// Random positions tried for each copy by `scatter_pattern` before it gives
+// up on finding room for the rest.
+const SCATTER_ATTEMPTS: u32 = 100;
+
+// Offsets to the rows or columns from `radius` before to `radius` after a
+// cell, in that order, on a universe that wraps around after `size` of them.
+// On universes less than `2 * radius + 1` cells wide or tall some of them are
+// the same, and are only kept once, so that a cell is never counted twice,
+// or as its own neighbour.
+fn neighbor_offsets(size: u32, radius: u32) -> Vec<u32> {
+    let mut offsets = Vec::with_capacity(2 * radius as usize + 1);
+    if size > 0 {
+        for delta in -(radius as i64)..=radius as i64 {
+            let offset = delta.rem_euclid(size as i64) as u32;
+            if !offsets.contains(&offset) {
+                offsets.push(offset);
+            }
+        }
+    }
+    offsets
+}
+
+// Starting value of a 64-bit FNV-1a hash, see `fnv1a`.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+// Side of the square of random cells that `soup_result` starts from, as in
+// the usual soup searches.
+const SOUP_SIZE: u32 = 16;
+
+// Feed `bytes` into a 64-bit FNV-1a hash, starting from `FNV_OFFSET`.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+// Cells relative to the top-left corner of their bounding box, in row-major
+// order, so that shapes can be compared regardless of their position.
+fn normalize_shape(mut cells: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    let min_row = cells.iter().map(|&(r, _)| r).min().unwrap_or(0);
+    let min_col = cells.iter().map(|&(_, c)| c).min().unwrap_or(0);
+    for cell in cells.iter_mut() {
+        *cell = (cell.0 - min_row, cell.1 - min_col);
+    }
+    cells.sort_unstable();
+    cells
+}
+
+// The 8 rotations and reflections of `cells`, each normalized.
+fn shape_variants(cells: &[(i64, i64)]) -> Vec<Vec<(i64, i64)>> {
+    let mut variants = Vec::with_capacity(8);
+    // Each symmetry of the square either swaps rows and columns or not,
+    // then negates either, both or neither of them.
+    for &swap in &[false, true] {
+        for &(sign_r, sign_c) in &[(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+            let transformed = cells
+                .iter()
+                .map(|&(r, c)| if swap { (c, r) } else { (r, c) })
+                .map(|(r, c)| (sign_r * r, sign_c * c))
+                .collect();
+            variants.push(normalize_shape(transformed));
+        }
+    }
+    variants
+}
+
+// Whichever of the `shape_variants` of `cells` comes first in lexicographic
+// order, which is the same for every rotation and reflection of them.
+fn canonical_shape(cells: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    shape_variants(cells).into_iter().min().unwrap_or_default()
+}
+
+// Whether `cells` have the same shape as the already normalized `target`,
+// up to their position and any of the 8 rotations and reflections.
+fn same_shape(cells: &[(i64, i64)], target: &[(i64, i64)]) -> bool {
+    cells.len() == target.len() && shape_variants(cells).iter().any(|variant| variant == target)
+}
+
+// Mean `(row, column)` of the cells of a group, as returned by `clusters`.
+fn centroid(cluster: &[(i64, i64)]) -> (f64, f64) {
+    let n = cluster.len() as f64;
+    let rows: i64 = cluster.iter().map(|&(r, _)| r).sum();
+    let cols: i64 = cluster.iter().map(|&(_, c)| c).sum();
+    (rows as f64 / n, cols as f64 / n)
+}
+
+/// Set how much diagnostic logging goes to the console:
+/// 0 - none, the default,
+/// 1 - informational messages,
+/// 2 - every cell that changes during a tick,
+/// 3 - every cell visited during a tick.
+#[wasm_bindgen]
+pub fn set_log_level(level: u8) {
+    utils::set_log_level(level);
+}
+
+/// Draw every random number from a pseudo-random generator seeded with
+/// `seed` instead of `Math.random()`, so that a whole session can be
+/// replayed: `Universe::new`, `reinitialize_rng`, and `seed_dead_cells`
+/// without a seed of its own, then give the same results every time.
+#[wasm_bindgen]
+pub fn use_seeded_rng(seed: u64) {
+    utils::set_seeded_rng(Some(seed));
+}
+
+/// Go back to drawing random numbers from `Math.random()`, the default, see
+/// `use_seeded_rng`.
+#[wasm_bindgen]
+pub fn use_system_rng() {
+    utils::set_seeded_rng(None);
+}
+
+/// How `combine_rules` combines two rules.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleOp {
+    /// Cells are born or survive with the neighbour counts of either rule.
+    Union = 0,
+    /// Cells are born or survive with the neighbour counts of both rules.
+    Intersection = 1,
+}
+
+/// Combine two rules in B/S notation, see `rules::parse` for the accepted
+/// forms, into one in the canonical notation, e.g. `B3/S23` and `B36/S23`
+/// give `B36/S23` as a union and `B3/S23` as an intersection.
+///
+/// Fails if either rule is invalid.
+#[wasm_bindgen]
+pub fn combine_rules(a: &str, b: &str, op: RuleOp) -> Result<String, Error> {
+    let parse = |rule: &str| rules::parse(rule).ok_or_else(|| Error::InvalidRule(rule.to_string()));
+    let (birth_a, survival_a) = parse(a)?;
+    let (birth_b, survival_b) = parse(b)?;
+    Ok(match op {
+        RuleOp::Union => rules::format(birth_a | birth_b, survival_a | survival_b),
+        RuleOp::Intersection => rules::format(birth_a & birth_b, survival_a & survival_b),
+    })
+}
+
+/// Tick `a` and `b` together `max_generations` times, and return the
+/// `hamming_distance` between them before the first tick and after each
+/// one, e.g. to watch how a one-cell change spreads across a board.
+///
+/// Fails without ticking either if they do not have the same dimensions and
+/// rule.
+#[wasm_bindgen]
+pub fn lockstep_divergence(a: &mut Universe, b: &mut Universe, max_generations: u32) -> Result<Vec<u32>, Error> {
+    if a.rule() != b.rule() {
+        return Err(Error::RuleMismatch { rule: a.rule(), other_rule: b.rule() });
+    }
+    let mut distances = vec![a.hamming_distance(b)?];
+
+    for _ in 0..max_generations {
+        a.tick();
+        b.tick();
+        distances.push(a.hamming_distance(b)?);
+    }
+    Ok(distances)
+}
+
+/// What lies beyond the edges of the universe.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Boundary {
+    /// The universe wraps around: the cells past the right edge are those of
+    /// the left edge, and the cells past the bottom edge those of the top
+    /// edge. This is the default.
+    Toroidal = 0,
+    /// Every cell past the edges is dead, and stays dead.
+    Dead = 1,
+}
+
 #[wasm_bindgen]
+#[derive(Clone)]
 // Entirety of the board in Conway's game of life.
 // It wraps around the edges, and is in practice represented as a single vector
 // of cells, and not a matrix, to ease integration into Wasm.
+// Any buffer added here should be accounted for in `memory_bytes`.
 pub struct Universe {
     width: u32,
     height: u32,
     cells: FixedBitSet,
+    // Rule masks: bit `n` of `birth` is set if a dead cell with `n` live
+    // neighbours becomes alive, and bit `n` of `survival` is set if a live
+    // cell with `n` live neighbours stays alive.
+    birth: u32,
+    survival: u32,
+    // How far the neighbourhood of a cell extends in each direction: 1 for
+    // the 8 surrounding cells of the B/S rules, 2 for the 24 of Life24.
+    radius: u32,
+    // Rule followed by the cells of a rectangle instead of the one above,
+    // if any.
+    rule_region: Option<RuleRegion>,
+    // Cells that `tick` leaves as they are, whatever the rule.
+    frozen: FixedBitSet,
+    // Langton's ant walking on the board, if any, see `place_ant`.
+    ant: Option<ant::LangtonAnt>,
+    boundary: Boundary,
+    // Cells that would have been born past the edges under a dead boundary,
+    // since the boundary was set or the board last cleared.
+    escaped: u32,
+    // Number of ticks since the universe was created.
+    generation: u32,
+    // Highest population reached by a tick, and the generation at which it
+    // was first reached.
+    peak_population: u32,
+    peak_generation: u32,
+    // Population after each tick, oldest first, keeping at most
+    // `history_limit` entries (no limit if it is 0).
+    history: VecDeque<u32>,
+    history_limit: u32,
+    // Cells born and cells that died during the last tick.
+    births: u32,
+    deaths: u32,
+    // Number of ticks in a row that left the board unchanged, and whether
+    // the board was edited or resized since the last tick, which every
+    // method changing `cells` other than a tick must record.
+    stable_for: u32,
+    edited: bool,
+    // Log a summary line every `log_interval` ticks (never if it is 0).
+    log_interval: u32,
+    // Generations advanced by each call to `tick_stepped`, at least 1.
+    steps_per_call: u32,
+    // Number of calls to `tick_with_injection` so far, which tells when to
+    // inject next.
+    injection_ticks: u32,
+    // State before each of the most recent ticks, oldest first, keeping at
+    // most `undo_limit` of them (none if it is 0).
+    undo: VecDeque<Snapshot>,
+    undo_limit: u32,
+    // Whether editing methods append to `script`, see `set_recording`.
+    recording: bool,
+    script: Vec<String>,
+    // Symbols for live and dead cells used by `render_emoji`.
+    emoji_alive: String,
+    emoji_dead: String,
+    // Offsets, modulo the height and the width, from a cell to the rows and
+    // columns of its neighbourhood, without duplicates, see
+    // `neighbor_offsets`. They must be updated whenever the dimensions
+    // change.
+    row_offsets: Vec<u32>,
+    col_offsets: Vec<u32>,
+    // Neighbour counts maintained by `tick` from one generation to the next,
+    // or `None` until the first tick.
+    neighbor_counts: Option<NeighborCounts>,
+}
+
+// Outcome of computing one generation.
+struct Step {
+    cells: FixedBitSet,
+    births: u32,
+    deaths: u32,
+}
+
+// Rule masks followed by the cells in `cells` instead of the universe's own.
+#[derive(Clone)]
+struct RuleRegion {
+    cells: FixedBitSet,
+    birth: u32,
+    survival: u32,
+}
+
+// What a tick changes, saved before it so that it can be undone.
+#[derive(Clone)]
+struct Snapshot {
+    width: u32,
+    height: u32,
+    cells: FixedBitSet,
+    generation: u32,
+    births: u32,
+    deaths: u32,
+}
+
+// Number of live neighbours of every cell, updated as cells change so that a
+// tick only has to look at the cells around those that changed, rather than
+// at the whole board.
+#[derive(Clone)]
+struct NeighborCounts {
+    // Dimensions and rule the counts were computed with; if the universe no
+    // longer matches them, the counts are computed again from scratch.
+    width: u32,
+    height: u32,
+    birth: u32,
+    survival: u32,
+    // Board the counts are for. Cells of the universe that differ from it
+    // have been edited since the last tick.
+    cells: FixedBitSet,
+    counts: Vec<u8>,
+    // Cells whose state may change in the next generation. Any other cell
+    // is known to stay as it is, since neither its state nor its number of
+    // live neighbours changed since it last stayed as it was.
+    active: FixedBitSet,
+}
+
+impl NeighborCounts {
+    // Flip the cell at `idx`, updating the counts of its neighbours and
+    // marking it and them as active.
+    fn flip(&mut self, universe: &Universe, idx: usize) {
+        self.cells.toggle(idx);
+        let alive = self.cells[idx];
+        self.active.insert(idx);
+        universe.for_each_neighbor(idx, |neighbor| {
+            if alive {
+                self.counts[neighbor] += 1;
+            } else {
+                self.counts[neighbor] -= 1;
+            }
+            self.active.insert(neighbor);
+        });
+    }
 }
 
 #[wasm_bindgen]
@@ -45,11 +402,61 @@ impl Universe {
         self.cells.as_slice().as_ptr()
     }
 
+    /// Number of `u32` words behind the pointer returned by `cells`.
+    ///
+    /// JavaScript should use this, rather than recomputing it from the
+    /// width and height, to know how far it may read into wasm memory.
+    pub fn cells_word_len(&self) -> usize {
+        self.cells.as_slice().len()
+    }
+
+    /// Approximate number of bytes of memory taken by the universe: the
+    /// cells, plus every buffer kept alongside them, such as the population
+    /// history, the undo snapshots, the edit script and the neighbour
+    /// counts of `tick`, e.g. to warn before a board gets too large for the
+    /// wasm memory.
+    pub fn memory_bytes(&self) -> usize {
+        use std::mem::{size_of, size_of_val};
+
+        let bits = |bits: &FixedBitSet| size_of_val(bits.as_slice());
+        let mut bytes = size_of::<Universe>() + bits(&self.cells) + bits(&self.frozen);
+        if let Some(region) = &self.rule_region {
+            bytes += bits(&region.cells);
+        }
+        bytes += self.history.capacity() * size_of::<u32>();
+        bytes += self.undo.capacity() * size_of::<Snapshot>();
+        bytes += self.undo.iter().map(|snapshot| bits(&snapshot.cells)).sum::<usize>();
+        bytes += self.script.capacity() * size_of::<String>();
+        bytes += self.script.iter().map(String::capacity).sum::<usize>();
+        bytes += self.emoji_alive.capacity() + self.emoji_dead.capacity();
+        bytes += (self.row_offsets.capacity() + self.col_offsets.capacity()) * size_of::<u32>();
+        if let Some(counts) = &self.neighbor_counts {
+            bytes += bits(&counts.cells) + counts.counts.capacity() + bits(&counts.active);
+        }
+        bytes
+    }
+
+    /// How the cells behind `cells` are laid out, for JavaScript to check
+    /// that it reads them the way it expects before reading any: the number
+    /// of bits per word in the lowest 8 bits, and `CELLS_LAYOUT_VERSION`
+    /// above them.
+    ///
+    /// In version 1, cell `i`, in row-major order, is bit `i % 32` of word
+    /// `i / 32`, counting from the least significant bit, and the words are
+    /// `u32`s in the little-endian order of wasm memory.
+    pub fn bit_layout() -> u32 {
+        CELLS_LAYOUT_VERSION << 8 | u32::BITS
+    }
+
     /// Set the width of the universe.
     ///
     /// Resets all cells to the dead state.
     pub fn set_width(&mut self, width: u32) {
         self.width = width;
+        self.col_offsets = neighbor_offsets(width, self.radius);
+        self.rule_region = None;
+        self.frozen.clear();
+        self.ant = None;
 
         let size = (width * self.height) as usize;
         self.cells.grow(size);
@@ -57,6 +464,7 @@ impl Universe {
         for i in 0..size {
             self.cells.set(i, false);
         }
+        self.edited = true;
     }
 
     /// Set the height of the universe.
@@ -64,6 +472,10 @@ impl Universe {
     /// Resets all cells to the dead state.
     pub fn set_height(&mut self, height: u32) {
         self.height = height;
+        self.row_offsets = neighbor_offsets(height, self.radius);
+        self.rule_region = None;
+        self.frozen.clear();
+        self.ant = None;
 
         let size = (self.width * height) as usize;
         self.cells.grow(size);
@@ -71,16 +483,118 @@ impl Universe {
         for i in 0..size {
             self.cells.set(i, false);
         }
+        self.edited = true;
+    }
+
+    /// Change the size of the universe, keeping its contents in the middle,
+    /// e.g. to give a pattern more room to evolve: growing adds as many new
+    /// rows and columns on each side, and shrinking crops as many on each
+    /// side. When that number is odd, the extra row or column is added or
+    /// removed at the bottom or right.
+    pub fn resize_centered(&mut self, new_width: u32, new_height: u32) {
+        let row_shift = (new_height as i64 - self.height as i64) / 2;
+        let col_shift = (new_width as i64 - self.width as i64) / 2;
+
+        let mut cells = FixedBitSet::with_capacity((new_width * new_height) as usize);
+        for (row, col) in self.live_cells() {
+            let (row, col) = (row as i64 + row_shift, col as i64 + col_shift);
+            if (0..new_height as i64).contains(&row) && (0..new_width as i64).contains(&col) {
+                cells.insert((row * new_width as i64 + col) as usize);
+            }
+        }
+
+        self.width = new_width;
+        self.height = new_height;
+        self.row_offsets = neighbor_offsets(new_height, self.radius);
+        self.col_offsets = neighbor_offsets(new_width, self.radius);
+        self.rule_region = None;
+        self.frozen.clear();
+        self.ant = None;
+        self.cells = cells;
+        self.edited = true;
     }
 
+    // Index of a cell in `cells`. The coordinates are not checked, so this
+    // is only for loops that already stay within bounds; anything handling
+    // outside coordinates should use `try_get_index` instead.
     fn get_index(&self, row: u32, column: u32) -> usize {
         (row * self.width + column) as usize
     }
 
+    // Index of a cell in `cells`, or `None` if it is outside of the universe.
+    fn try_get_index(&self, row: u32, column: u32) -> Option<usize> {
+        if row < self.height && column < self.width {
+            Some(self.get_index(row, column))
+        } else {
+            None
+        }
+    }
+
+    // Index of the cell at `(row + r, column + c)` in `cells`, or `None` if it
+    // is outside of the universe, including when the sums overflow.
+    fn try_get_index_offset(&self, row: u32, r: u32, column: u32, c: u32) -> Option<usize> {
+        self.try_get_index(row.checked_add(r)?, column.checked_add(c)?)
+    }
+
+    // Index of a cell in `cells`, or an error if it is outside of the
+    // universe.
+    fn checked_index(&self, row: u32, column: u32) -> Result<usize, Error> {
+        self.try_get_index(row, column).ok_or(Error::OutOfBounds {
+            row,
+            column,
+            width: self.width,
+            height: self.height,
+        })
+    }
+
+    /// Whether the cell at `(row, column)` is alive.
+    ///
+    /// Fails if the cell is outside of the universe.
+    pub fn get_cell(&self, row: u32, column: u32) -> Result<bool, Error> {
+        Ok(self.cells[self.checked_index(row, column)?])
+    }
+
+    /// Number of live neighbours of the cell at `(row, column)`, as counted
+    /// by `tick`, e.g. to annotate each cell with it.
+    ///
+    /// Fails if the cell is outside of the universe.
+    pub fn neighbor_count(&self, row: u32, column: u32) -> Result<u8, Error> {
+        self.checked_index(row, column)?;
+        Ok(self.live_neighbor_count(row, column))
+    }
+
+    /// Number of live neighbours of every cell, as counted by `tick`, row
+    /// by row, e.g. to shade the board by what the next generation makes of
+    /// each cell.
+    pub fn neighbor_sums(&self) -> Vec<u8> {
+        let mut sums = Vec::with_capacity((self.width * self.height) as usize);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                sums.push(self.live_neighbor_count(row, col));
+            }
+        }
+        sums
+    }
+
+    /// Make the cell at `(row, column)` alive or dead.
+    ///
+    /// Fails if the cell is outside of the universe.
+    pub fn set_cell(&mut self, row: u32, column: u32, alive: bool) -> Result<(), Error> {
+        let idx = self.checked_index(row, column)?;
+        self.cells.set(idx, alive);
+        self.edited = true;
+        self.record(|| format!("set_cell {} {} {}", row, column, alive as u8));
+        Ok(())
+    }
+
     fn live_neighbor_count(&self, row: u32, column: u32) -> u8 {
+        if self.boundary == Boundary::Dead {
+            return self.bounded_neighbor_count(row as i64, column as i64);
+        }
+
         let mut count = 0;
-        for delta_row in [self.height - 1, 0, 1].iter().cloned() {
-            for delta_col in [self.width - 1, 0, 1].iter().cloned() {
+        for &delta_row in &self.row_offsets {
+            for &delta_col in &self.col_offsets {
                 if delta_row == 0 && delta_col == 0 {
                     continue;
                 }
@@ -94,48 +608,97 @@ impl Universe {
         count
     }
 
-    pub fn tick(&mut self) {
-        let mut next = self.cells.clone();
+    // Decide whether a cell is alive in the next generation, from its current
+    // state and its number of live neighbours, according to the universe's
+    // birth and survival rule masks.
+    //
+    // With the default masks this is Conway's rule:
+    // 1. Any live cell with fewer than two live neighbours dies, as if caused
+    //    by underpopulation.
+    // 2. Any live cell with two or three live neighbours lives on to the next
+    //    generation.
+    // 3. Any live cell with more than three live neighbours dies, as if by
+    //    overpopulation.
+    // 4. Any dead cell with exactly three live neighbours becomes a live
+    //    cell, as if by reproduction.
+    fn next_cell_state(&self, cell: bool, neighbors: u8) -> bool {
+        let mask = if cell { self.survival } else { self.birth };
+        mask & (1 << neighbors) != 0
+    }
 
-        for row in 0..self.height {
-            for col in 0..self.width {
+    // Like `next_cell_state`, for the cell at `idx`, which follows the rule
+    // of the region set with `set_rule_region` if it lies within it, and
+    // keeps its state if it is frozen.
+    fn next_cell_state_at(&self, idx: usize, cell: bool, neighbors: u8) -> bool {
+        if self.frozen[idx] {
+            return cell;
+        }
+        match &self.rule_region {
+            Some(region) if region.cells[idx] => {
+                let mask = if cell { region.survival } else { region.birth };
+                mask & (1 << neighbors) != 0
+            }
+            _ => self.next_cell_state(cell, neighbors),
+        }
+    }
+
+    // Compute the cells of the next generation, without modifying the
+    // universe. Shared by every method that advances or previews a tick.
+    fn next_generation(&self) -> Step {
+        self.next_generation_within(0, 0, self.height, self.width)
+    }
+
+    // Like `next_generation`, but only the cells of the `height` by `width`
+    // rectangle whose top-left cell is `(row, col)`, clipped to the
+    // universe, are updated: the others keep their state.
+    fn next_generation_within(&self, row: u32, col: u32, height: u32, width: u32) -> Step {
+        let mut step = Step {
+            cells: self.cells.clone(),
+            births: 0,
+            deaths: 0,
+        };
+
+        // Nothing can be born on an empty board, unless the rule, or that of
+        // the rule region, makes cells appear out of nothing (B0).
+        let region_b0 = self.rule_region.as_ref().is_some_and(|region| region.birth & 1 != 0);
+        if self.birth & 1 == 0 && !region_b0 && self.population() == 0 {
+            return step;
+        }
+
+        let next = &mut step.cells;
+
+        let rows = row.min(self.height)..row.saturating_add(height).min(self.height);
+        let cols = col.min(self.width)..col.saturating_add(width).min(self.width);
+        for row in rows {
+            for col in cols.clone() {
                 let idx = self.get_index(row, col);
                 let cell = self.cells[idx];
                 let live_neighbors = self.live_neighbor_count(row, col);
 
-                /*
                 log!(
-                     "cell[{}, {}] is initially {:?} and has {} live neighbors",
-                     row,
-                     col,
-                     cell,
-                     live_neighbors
-                 );
-                */
-
-                let next_cell = match (cell, live_neighbors) {
-                    // Rule 1: Any live cell with fewer than two live neighbours
-                    // dies, as if caused by underpopulation.
-                    (true, x) if x < 2 => false,
-                    // Rule 2: Any live cell with two or three live neighbours
-                    // lives on to the next generation.
-                    (true, 2) | (true, 3) => true,
-                    // Rule 3: Any live cell with more than three live
-                    // neighbours dies, as if by overpopulation.
-                    (true, x) if x > 3 => false,
-                    // Rule 4: Any dead cell with exactly three live neighbours
-                    // becomes a live cell, as if by reproduction.
-                    (false, 3) => true,
-                    // All other cells remain in the same state.
-                    (otherwise, _) => otherwise,
-                };
+                    utils::LOG_TRACE,
+                    "cell[{}, {}] is initially {:?} and has {} live neighbors",
+                    row,
+                    col,
+                    cell,
+                    live_neighbors
+                );
 
-                //log!("    it becomes {:?}", next_cell);
+                let next_cell = self.next_cell_state_at(idx, cell, live_neighbors);
+
+                log!(utils::LOG_TRACE, "    it becomes {:?}", next_cell);
 
                 next.set(idx, next_cell);
 
                 if self.cells[idx] != next[idx] {
+                    if next_cell {
+                        step.births += 1;
+                    } else {
+                        step.deaths += 1;
+                    }
+
                     log!(
+                        utils::LOG_DEBUG,
                         "cell[{}, {}] is initially {:?} and became {}",
                         row,
                         col,
@@ -146,179 +709,4585 @@ impl Universe {
             }
         }
 
-        self.cells = next;
+        step
     }
-}
 
-use std::fmt;
-
-impl fmt::Display for Universe {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for row in 0..self.height {
-            for col in 0..self.width {
-                let index = self.get_index(row, col);
-                let cell = self.cells[index];
-                let symbol = if cell { '◼' } else { '◻' };
-                write!(f, "{}", symbol)?;
+    // Number of live neighbours of the cell at `(row, column)`, which may lie
+    // past the edges, counting only cells within the universe.
+    fn bounded_neighbor_count(&self, row: i64, column: i64) -> u8 {
+        let radius = self.radius as i64;
+        let mut count = 0;
+        for neighbor_row in row - radius..=row + radius {
+            for neighbor_col in column - radius..=column + radius {
+                if (neighbor_row, neighbor_col) == (row, column) {
+                    continue;
+                }
+                if (0..self.height as i64).contains(&neighbor_row)
+                    && (0..self.width as i64).contains(&neighbor_col)
+                {
+                    let idx = self.get_index(neighbor_row as u32, neighbor_col as u32);
+                    count += self.cells[idx] as u8;
+                }
             }
-            write!(f, "\n")?;
         }
-
-        Ok(())
+        count
     }
-}
 
-/// Public methods, exported to JavaScript.
-/// This `impl` block is mostly for constructors.
-#[wasm_bindgen]
-impl Universe {
-    pub fn toggle_cell(&mut self, row: u32, column: u32) {
-        let idx = self.get_index(row, column);
-        self.cells.set(idx, !self.cells[idx]);
+    // Number of cells past the edges, within the neighbourhood radius of
+    // the universe, that would be born in the next generation if there were
+    // more board there.
+    fn escaping_cells(&self) -> u32 {
+        let radius = self.radius as i64;
+        let (height, width) = (self.height as i64, self.width as i64);
+        let mut escaping = 0;
+        for row in -radius..height + radius {
+            for col in -radius..width + radius {
+                let outside = row < 0 || row >= height || col < 0 || col >= width;
+                if outside && self.next_cell_state(false, self.bounded_neighbor_count(row, col)) {
+                    escaping += 1;
+                }
+            }
+        }
+        escaping
     }
 
-    // Deterministic universe with random cell states, 64 by 64.
-    pub fn hardcoded_64_by_64() -> Universe {
+    // Call `f` with the index of each neighbour of the cell at `idx`, in the
+    // same order as `live_neighbor_count` visits them.
+    fn for_each_neighbor(&self, idx: usize, mut f: impl FnMut(usize)) {
+        let row = idx as u32 / self.width;
+        let column = idx as u32 % self.width;
+        for &delta_row in &self.row_offsets {
+            for &delta_col in &self.col_offsets {
+                if delta_row == 0 && delta_col == 0 {
+                    continue;
+                }
 
-        utils::set_panic_hook();
-        let width = 64;
-        let height = 64;
+                let neighbor_row = (row + delta_row) % self.height;
+                let neighbor_col = (column + delta_col) % self.width;
+                f(self.get_index(neighbor_row, neighbor_col));
+            }
+        }
+    }
 
-        let size = (width * height) as usize;
+    // Neighbour counts of the current board, computed from scratch, with
+    // every cell active.
+    fn count_neighbors(&self) -> NeighborCounts {
+        let size = (self.width * self.height) as usize;
         let mut cells = FixedBitSet::with_capacity(size);
-
-        for i in 0..size {
-            cells.set(i, i % 2 == 0 || i % 7 == 0);
+        let mut counts = vec![0; size];
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                cells.set(idx, self.cells[idx]);
+                counts[idx] = self.live_neighbor_count(row, col);
+            }
         }
 
-        Universe {
-            width,
-            height,
+        let mut active = FixedBitSet::with_capacity(size);
+        active.insert_range(..);
+
+        NeighborCounts {
+            width: self.width,
+            height: self.height,
+            birth: self.birth,
+            survival: self.survival,
             cells,
+            counts,
+            active,
         }
     }
 
-    // Create a universe with a random initial position, in a non-deterministic
-    // manner unlike the method above.
-    // The grid's dimensions are passed as an argument.
-    pub fn new(width : u32, height : u32) -> Universe {
-        utils::set_panic_hook();
-
-        let size = (width * height) as usize;
-        let mut cells = FixedBitSet::with_capacity(size);
-        for i in 0 .. size {
-                if js_sys::Math::random() < 0.5 {
-                    cells.set(i, true);
-                } else {
-                    cells.set(i, false);
-                }
-            };
+    // Advance the cells by one generation like `next_generation`, but in
+    // place, using and maintaining `neighbor_counts`: only the cells around
+    // those that changed during the previous tick, or were edited since,
+    // are looked at. Returns the number of births and deaths.
+    fn step_incremental(&mut self) -> (u32, u32) {
+        let mut counts = match self.neighbor_counts.take() {
+            Some(counts)
+                if counts.width == self.width
+                    && counts.height == self.height
+                    && counts.birth == self.birth
+                    && counts.survival == self.survival =>
+            {
+                counts
+            }
+            _ => self.count_neighbors(),
+        };
 
-        /*
-        Exercise 2 of the debugging chapter.
-        Uncomment this, comment the marked line in Cargo.toml, and run `wasm-pack build --release`.
+        // Catch up with the cells edited since the last tick.
+        let edited: Vec<usize> = self
+            .logical_words()
+            .zip(counts.cells.as_slice().iter())
+            .enumerate()
+            .flat_map(|(i, (word, counted))| {
+                let diff = word ^ counted;
+                (0..32).filter(move |bit| diff & (1 << bit) != 0).map(move |bit| i * 32 + bit)
+            })
+            .collect();
+        for idx in edited {
+            counts.flip(self, idx);
+        }
 
-        panic!("Just for testing purposes");
-        */
+        let changed: Vec<usize> = counts
+            .active
+            .ones()
+            .filter(|&idx| {
+                self.next_cell_state_at(idx, counts.cells[idx], counts.counts[idx]) != counts.cells[idx]
+            })
+            .collect();
+        counts.active.clear();
 
-        Universe {
-            width,
-            height,
-            cells,
+        let (mut births, mut deaths) = (0, 0);
+        for idx in changed {
+            counts.flip(self, idx);
+            if counts.cells[idx] {
+                births += 1;
+            } else {
+                deaths += 1;
+            }
+            self.cells.set(idx, counts.cells[idx]);
         }
-    }
 
-    pub fn clear_all_cells(&mut self) {
-        for i in 0 .. (self.width() * self.height()) as usize {
-            self.cells.set(i, false)
-        };
+        self.neighbor_counts = Some(counts);
+        (births, deaths)
     }
 
-    // Reinitialize an already existing universe with a random state.
-    pub fn reinitialize_rng(&mut self) {
-        let size = (self.width() * self.height()) as usize;
+    /// Advance the universe by one generation.
+    ///
+    /// On an empty board this returns without scanning the cells, but still
+    /// counts as a generation: the generation counter advances and the
+    /// population history records a 0.
+    pub fn tick(&mut self) {
+        self.advance(None);
+    }
 
-        for i in 0 .. size {
-            if js_sys::Math::random() < 0.5 {
-                self.cells.set(i, true);
-            } else {
-                self.cells.set(i, false);
-            }
-        };
+    /// Advance the universe by one generation like `tick`, but only update
+    /// the cells of the `height` by `width` rectangle whose top-left cell
+    /// is `(row, col)`, clipped to the universe, e.g. when a UI knows where
+    /// the action is and the rest of a large board is still.
+    ///
+    /// The cells outside of the rectangle keep their state, but still count
+    /// as neighbours of those inside. The result is the same as a `tick`
+    /// only as long as nothing outside of the rectangle would have changed:
+    /// activity that escapes it, or starts elsewhere, is frozen.
+    pub fn tick_region(&mut self, row: u32, col: u32, height: u32, width: u32) {
+        self.advance(Some((row, col, height, width)));
     }
 
-    // Given an array of `u8`s representting the cells' neighbourhood,
-    // set that cell's neighbours to the values in the array.
-    // Because an array or vector of `bool`s do not implement the `FromWasmAbi`
-    // trait, we must instead use `u8`s.
-    pub fn set_pattern_at(&mut self, row: u32, column: u32, neighbourhood : &[u8]) {
-        let mut i = 0;
+    // Shared by `tick` and `tick_region`, which passes the rectangle it
+    // updates as `(row, col, height, width)`.
+    fn advance(&mut self, region: Option<(u32, u32, u32, u32)>) {
+        if self.undo_limit > 0 {
+            self.undo.push_back(Snapshot {
+                width: self.width,
+                height: self.height,
+                cells: self.cells.clone(),
+                generation: self.generation,
+                births: self.births,
+                deaths: self.deaths,
+            });
+            self.trim_undo();
+        }
 
-        for delta_row in [self.height - 1, 0, 1].iter().cloned() {
-            for delta_col in [self.width - 1, 0, 1].iter().cloned() {
-                let neighbor_row = (row + delta_row) % self.height;
-                let neighbor_col = (column + delta_col) % self.width;
-                let idx = self.get_index(neighbor_row, neighbor_col);
-                let cell = if neighbourhood[i] > 0 { true } else { false };
-                self.cells.set(idx, cell);
-                i += 1;
-            }
+        // Account for the board being replaced, which may have been edited
+        // since the last tick.
+        let (peak_population, peak_generation) = self.peak_population();
+        self.peak_population = peak_population;
+        self.peak_generation = peak_generation;
+
+        if self.boundary == Boundary::Dead {
+            self.escaped += self.escaping_cells();
         }
-    }
 
-    // Create one instance of Gosper's glider at the center of
+        // Per-cell logging needs every cell to be visited, which the
+        // incremental update avoids, and the incremental update only knows
+        // about the 8 surrounding cells of a universe that wraps around and
+        // follows a single rule everywhere.
+        if utils::log_enabled(utils::LOG_DEBUG)
+            || self.radius != 1
+            || self.boundary != Boundary::Toroidal
+            || self.rule_region.is_some()
+            || !self.frozen.is_clear()
+            || region.is_some()
+        {
+            let (row, col, height, width) = region.unwrap_or((0, 0, self.height, self.width));
+            let step = self.next_generation_within(row, col, height, width);
+            self.cells = step.cells;
+            self.births = step.births;
+            self.deaths = step.deaths;
+        } else {
+            let (births, deaths) = self.step_incremental();
+            self.births = births;
+            self.deaths = deaths;
+        }
+        self.generation += 1;
+
+        if self.births + self.deaths > 0 {
+            self.stable_for = 0;
+        } else if self.edited {
+            self.stable_for = 1;
+        } else {
+            self.stable_for += 1;
+        }
+        self.edited = false;
+
+        let population = self.population();
+        if population > self.peak_population {
+            self.peak_population = population;
+            self.peak_generation = self.generation;
+        }
+
+        self.history.push_back(population);
+        self.trim_history();
+
+        if self.log_interval > 0 && self.generation.is_multiple_of(self.log_interval) {
+            log!(
+                utils::LOG_ALWAYS,
+                "generation {}: population {}, {} births, {} deaths",
+                self.generation,
+                population,
+                self.births,
+                self.deaths
+            );
+        }
+    }
+
+    /// Update every row on its own as a one-dimensional elementary cellular
+    /// automaton following Wolfram's `rule`: the new state of a cell is bit
+    /// `4 * left + 2 * cell + right` of `rule`, where `left` and `right` are
+    /// its neighbours in the row, wrapping around its ends, e.g. rule 30 or
+    /// 110, to draw textures of those automata.
+    ///
+    /// The cells above and below are ignored entirely, and so are the Life
+    /// rule and boundary. This is not a tick: the generation counter and
+    /// the statistics of `tick` are left as they are.
+    pub fn step_elementary(&mut self, rule: u8) {
+        let mut next = FixedBitSet::with_capacity((self.width * self.height) as usize);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let left = self.cells[self.get_index(row, (col + self.width - 1) % self.width)] as u8;
+                let cell = self.cells[self.get_index(row, col)] as u8;
+                let right = self.cells[self.get_index(row, (col + 1) % self.width)] as u8;
+                next.set(self.get_index(row, col), (rule >> (left << 2 | cell << 1 | right)) & 1 != 0);
+            }
+        }
+        self.cells = next;
+        self.edited = true;
+    }
+
+    /// Advance the universe by one generation, like `tick`, then bring the
+    /// cell at `(row, column)` back to life if it died, as if it were a
+    /// constant source of live cells, e.g. to watch what grows out of it.
+    /// See `freeze_cell` to hold several cells in either state.
+    ///
+    /// Fails, without ticking, if the cell is outside of the universe.
+    pub fn tick_with_source(&mut self, row: u32, column: u32) -> Result<(), Error> {
+        let idx = self.checked_index(row, column)?;
+        self.tick();
+        if !self.cells.put(idx) {
+            self.edited = true;
+        }
+        Ok(())
+    }
+
+    /// Advance the universe by one generation, like `tick`, and return what
+    /// happened to each cell, in the same row-major order as `cells`: 0 if
+    /// it stayed dead, 1 if it was born, 2 if it died and 3 if it stayed
+    /// alive, e.g. to color births and deaths differently.
+    pub fn activity_map(&mut self) -> Vec<u8> {
+        let before = self.cells.clone();
+        self.tick();
+        (0..(self.width * self.height) as usize)
+            .map(|idx| match (before[idx], self.cells[idx]) {
+                (false, false) => 0,
+                (false, true) => 1,
+                (true, false) => 2,
+                (true, true) => 3,
+            })
+            .collect()
+    }
+
+    /// Advance the universe by `generations` ticks, counting for each cell
+    /// how many times it was born or died along the way, and return the
+    /// counts in the same row-major order as `cells`, e.g. to draw a heat
+    /// map of where the activity was.
+    pub fn heat_map(&mut self, generations: u32) -> Vec<u32> {
+        let mut heat = vec![0; (self.width * self.height) as usize];
+        for _ in 0..generations {
+            let mut changed = self.cells.clone();
+            self.tick();
+            changed ^= &self.cells;
+            for idx in changed.ones() {
+                heat[idx] += 1;
+            }
+        }
+        heat
+    }
+
+    /// Advance the universe by `generations` ticks, like `heat_map`, and
+    /// return how many times cells were born or died in each `tile` by
+    /// `tile` block of the board, e.g. for a renderer to redraw quiet
+    /// blocks less often than busy ones.
+    ///
+    /// The blocks come row by row, `width.div_ceil(tile)` of them per row of
+    /// blocks, the last ones in each row and column being cut short when
+    /// `tile` does not divide the dimensions. A `tile` of 0 is treated as 1.
+    pub fn region_activity(&mut self, tile: u32, generations: u32) -> Vec<u32> {
+        let tile = tile.max(1);
+        let tiles_across = self.width.div_ceil(tile);
+        let mut activity = vec![0; (tiles_across * self.height.div_ceil(tile)) as usize];
+        for (idx, heat) in self.heat_map(generations).into_iter().enumerate() {
+            let (row, col) = (idx as u32 / self.width, idx as u32 % self.width);
+            activity[((row / tile) * tiles_across + col / tile) as usize] += heat;
+        }
+        activity
+    }
+
+    /// Advance the universe by one generation, like `tick`, and return the
+    /// indices into `cells` of the cells that were born or died, in
+    /// increasing order.
+    ///
+    /// This is enough to record a run compactly: store a keyframe of the
+    /// board, e.g. from `to_bytes`, then the delta of each tick. Playing it
+    /// back means loading the keyframe, then passing each delta in turn to
+    /// `apply_delta`.
+    pub fn tick_delta(&mut self) -> Vec<u32> {
+        let mut changed = self.cells.clone();
+        self.tick();
+        changed ^= &self.cells;
+        changed.ones().map(|idx| idx as u32).collect()
+    }
+
+    /// Advance the universe by `generations` ticks, and return about how
+    /// many bytes a recording of them as described in `tick_delta` would
+    /// take, e.g. for a UI to tell whether to record a run: the keyframe
+    /// from `to_bytes`, then each delta as its length followed by its
+    /// indices, 4 bytes each. Nothing is recorded, and how the deltas are
+    /// actually stored is up to the caller, so this is only an estimate.
+    pub fn estimate_recording_size(&mut self, generations: u32) -> usize {
+        let mut size = self.to_bytes().len();
+        for _ in 0..generations {
+            self.tick();
+            size += 4 * (1 + (self.births + self.deaths) as usize);
+        }
+        size
+    }
+
+    /// Flip the cells at the given indices into `cells`, as returned by
+    /// `tick_delta`, to go from one recorded frame to the next. Only the
+    /// cells change: the generation counter and the statistics of `tick`
+    /// are left as they are.
+    ///
+    /// Fails, without flipping any cell, if an index is outside of the
+    /// universe.
+    pub fn apply_delta(&mut self, delta: &[u32]) -> Result<(), Error> {
+        let size = self.width * self.height;
+        if let Some(&idx) = delta.iter().find(|&&idx| idx >= size) {
+            let width = self.width.max(1);
+            return Err(Error::OutOfBounds {
+                row: idx / width,
+                column: idx % width,
+                width: self.width,
+                height: self.height,
+            });
+        }
+
+        for &idx in delta {
+            self.cells.toggle(idx as usize);
+        }
+        self.edited = true;
+        Ok(())
+    }
+
+    /// The rule in use, in B/S notation, e.g. `B3/S23` for Conway's game
+    /// of life, or `Life24`.
+    pub fn rule(&self) -> String {
+        if self.radius == 2 {
+            rules::LIFE24.to_string()
+        } else {
+            rules::format(self.birth, self.survival)
+        }
+    }
+
+    /// The usual name of the rule in use, e.g. `HighLife` for `B36/S23`, or
+    /// `Life24`, for a UI to show instead of its notation, or `None` if it
+    /// is not a well-known rule. The rule of a region set with
+    /// `set_rule_region` is not taken into account.
+    pub fn rule_name(&self) -> Option<String> {
+        if self.radius == 2 {
+            Some(rules::LIFE24.to_string())
+        } else {
+            rules::name(self.birth, self.survival).map(str::to_string)
+        }
+    }
+
+    /// The rule in use in plain English, e.g. `A cell is born with 3
+    /// neighbors; survives with 2 or 3 neighbors` for Conway's game of life,
+    /// to check that a rule given to `set_rule` means what it was meant to.
+    /// The rule of a region set with `set_rule_region` is not included.
+    pub fn explain_rule(&self) -> String {
+        rules::describe(self.birth, self.survival)
+    }
+
+    /// A one-line summary of how the universe evolves, for a UI to show:
+    /// its `boundary`, its neighbourhood, which is always the square Moore
+    /// neighbourhood, its `rule` and the radius of the neighbourhood, e.g.
+    /// `Toroidal, Moore, B3/S23, radius 1`.
+    ///
+    /// Frozen cells and the rule of a region set with `set_rule_region`
+    /// are not included.
+    pub fn describe_config(&self) -> String {
+        format!("{:?}, Moore, {}, radius {}", self.boundary, self.rule(), self.radius)
+    }
+
+    /// Change the rule used by `tick`, given in B/S notation such as
+    /// `B36/S23`, see `rules::parse` for the accepted forms, or as
+    /// `Life24` for the radius 2 rule described in `rules::LIFE24`.
+    ///
+    /// The universe is left unchanged if the rule is invalid.
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), Error> {
+        let (birth, survival, radius) = if rule.trim() == rules::LIFE24 {
+            (rules::LIFE24_BIRTH, rules::LIFE24_SURVIVAL, 2)
+        } else {
+            let (birth, survival) =
+                rules::parse(rule).ok_or_else(|| Error::InvalidRule(rule.to_string()))?;
+            (birth, survival, 1)
+        };
+        self.birth = birth;
+        self.survival = survival;
+        self.set_radius(radius);
+        self.record(|| format!("set_rule {}", rule.trim()));
+        log!(utils::LOG_INFO, "rule set to {}", self.rule());
+        Ok(())
+    }
+
+    fn set_radius(&mut self, radius: u32) {
+        self.radius = radius;
+        self.row_offsets = neighbor_offsets(self.height, radius);
+        self.col_offsets = neighbor_offsets(self.width, radius);
+    }
+
+    /// Make the cells of the `height` by `width` rectangle whose top-left
+    /// cell is `(row, col)` follow `rule`, given in B/S notation, while the
+    /// rest of the board keeps following the universe's rule, e.g. to build
+    /// walls of a different rule. Neighbours are counted across the border
+    /// of the region as everywhere else.
+    ///
+    /// The rectangle is clipped to the bounds of the universe. Only one
+    /// region can be set at a time: this replaces the previous one. It is
+    /// removed by `clear_rule_region`, and when the universe is resized or
+    /// transposed. The universe is left unchanged if the rule is invalid.
+    pub fn set_rule_region(&mut self, rule: &str, row: u32, col: u32, height: u32, width: u32) -> Result<(), Error> {
+        let (birth, survival) =
+            rules::parse(rule).ok_or_else(|| Error::InvalidRule(rule.to_string()))?;
+
+        let mut cells = FixedBitSet::with_capacity((self.width * self.height) as usize);
+        for r in row.min(self.height)..row.saturating_add(height).min(self.height) {
+            let start = self.get_index(r, col.min(self.width));
+            let end = self.get_index(r, col.saturating_add(width).min(self.width));
+            cells.insert_range(start..end);
+        }
+
+        self.rule_region = Some(RuleRegion { cells, birth, survival });
+        Ok(())
+    }
+
+    /// Make the whole board follow the universe's rule again, see
+    /// `set_rule_region`.
+    pub fn clear_rule_region(&mut self) {
+        self.rule_region = None;
+    }
+
+    /// Freeze the cell at `(row, column)`, so that `tick` leaves it in its
+    /// current state whatever the rule, e.g. to build walls that gliders
+    /// crash into. Frozen cells can still be edited, and count as
+    /// neighbours as usual. Every cell is unfrozen when the universe is
+    /// resized or transposed.
+    ///
+    /// Fails if the cell is outside of the universe.
+    pub fn freeze_cell(&mut self, row: u32, column: u32) -> Result<(), Error> {
+        let idx = self.checked_index(row, column)?;
+        self.frozen.grow((self.width * self.height) as usize);
+        self.frozen.insert(idx);
+        Ok(())
+    }
+
+    /// Let `tick` update the cell at `(row, column)` again, see
+    /// `freeze_cell`.
+    ///
+    /// Fails if the cell is outside of the universe.
+    pub fn unfreeze_cell(&mut self, row: u32, column: u32) -> Result<(), Error> {
+        let idx = self.checked_index(row, column)?;
+        self.frozen.set(idx, false);
+        Ok(())
+    }
+
+    /// Return a copy of this universe advanced by `generations` ticks under
+    /// `rule` instead of the current rule, leaving this universe unchanged.
+    ///
+    /// Handy to compare how different rules evolve the same board.
+    pub fn evolve_with_rule(&self, rule: &str, generations: u32) -> Result<Universe, Error> {
+        let mut universe = self.clone();
+        universe.set_rule(rule)?;
+        for _ in 0..generations {
+            universe.tick();
+        }
+        Ok(universe)
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    /// Choose what lies beyond the edges of the universe, see `Boundary`.
+    /// This resets `escaped_count`.
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+        self.escaped = 0;
+    }
+
+    /// Under a dead boundary, an estimate of how much has flown off the
+    /// edges, e.g. the output of a gun on a finite board: the number of
+    /// cells that ticks would have brought to life just past the edges, had
+    /// the board been larger.
+    ///
+    /// This counts cells, not patterns: a glider flying into an edge adds a
+    /// few cells over the ticks it takes to hit it, the exact number
+    /// depending on its phase and on what it leaves behind. So it is only
+    /// meaningful to compare runs of the same kind of spaceship. It stays 0
+    /// on a universe that wraps around, and is reset to 0 by `set_boundary`
+    /// and `clear_all_cells`.
+    pub fn escaped_count(&self) -> u32 {
+        self.escaped
+    }
+
+    /// Number of ticks since the universe was created.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Number of ticks in a row that changed no cell, e.g. to pause once a
+    /// board has settled into still lifes. It is back to 0 as soon as a
+    /// tick changes a cell, or the board is edited or resized.
+    pub fn stable_for(&self) -> u32 {
+        if self.edited {
+            0
+        } else {
+            self.stable_for
+        }
+    }
+
+    /// Number of dead cells that became alive during the last tick.
+    pub fn last_tick_births(&self) -> u32 {
+        self.births
+    }
+
+    /// Number of live cells that died during the last tick.
+    pub fn last_tick_deaths(&self) -> u32 {
+        self.deaths
+    }
+
+    /// Population after each tick, oldest first.
+    ///
+    /// Only the most recent `history_limit` entries are kept, if a limit is
+    /// set.
+    pub fn population_history(&self) -> Vec<u32> {
+        self.history.iter().cloned().collect()
+    }
+
+    /// Autocorrelation of the `population_history` at each lag from 0 to
+    /// `max_lag`, normalized so that it is 1 at lag 0: peaks at other lags
+    /// are the periods at which the population oscillates, e.g. 2 for a
+    /// board of beacons, whose population alternates between 6 and 8.
+    ///
+    /// Lags are only given up to one less than the length of the history.
+    /// The result is empty when the history holds fewer than 2 entries, or
+    /// the population never changed, which leaves nothing to correlate.
+    pub fn population_autocorrelation(&self, max_lag: u32) -> Vec<f64> {
+        let count = self.history.len();
+        let mean = self.history.iter().map(|&p| p as f64).sum::<f64>() / count as f64;
+        let deviations: Vec<f64> = self.history.iter().map(|&p| p as f64 - mean).collect();
+        let variance: f64 = deviations.iter().map(|d| d * d).sum();
+        if count < 2 || variance == 0.0 {
+            return Vec::new();
+        }
+
+        (0..=(max_lag as usize).min(count - 1))
+            .map(|lag| {
+                let covariance: f64 = deviations.iter().zip(&deviations[lag..]).map(|(a, b)| a * b).sum();
+                covariance / variance
+            })
+            .collect()
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    pub fn history_limit(&self) -> u32 {
+        self.history_limit
+    }
+
+    /// Set how many entries the population history keeps, dropping the
+    /// oldest ones beyond that. 0, the default, means no limit.
+    pub fn set_history_limit(&mut self, limit: u32) {
+        self.history_limit = limit;
+        self.trim_history();
+    }
+
+    fn trim_history(&mut self) {
+        if self.history_limit > 0 {
+            while self.history.len() > self.history_limit as usize {
+                self.history.pop_front();
+            }
+        }
+    }
+
+    pub fn log_interval(&self) -> u32 {
+        self.log_interval
+    }
+
+    /// Log a one-line summary of the board every `interval` ticks, whatever
+    /// the level set with `set_log_level`. This follows a long run without
+    /// flooding the console like per-cell logging does. 0, the default,
+    /// turns it off.
+    pub fn set_log_interval(&mut self, interval: u32) {
+        self.log_interval = interval;
+    }
+
+    /// Advance the universe by `n` generations, like calling `tick` `n`
+    /// times.
+    pub fn tick_n(&mut self, n: u32) {
+        for _ in 0..n {
+            self.tick();
+        }
+    }
+
+    /// Advance the universe by `generations` generations, like `tick_n`,
+    /// without any of the bookkeeping it does on the side, for batch runs
+    /// where only the end result matters, and return a copy of the board
+    /// reached.
+    ///
+    /// The ticks can't be undone with `rewind` and are left out of the
+    /// population history, which stays as it was, and no summary is logged
+    /// for them. Rewinding afterwards goes back to the states saved before
+    /// them.
+    pub fn fast_forward(&mut self, generations: u32) -> Universe {
+        let (undo_limit, history_limit, log_interval) = (self.undo_limit, self.history_limit, self.log_interval);
+        let history = std::mem::take(&mut self.history);
+        self.undo_limit = 0;
+        self.history_limit = 1;
+        self.log_interval = 0;
+
+        self.tick_n(generations);
+
+        self.undo_limit = undo_limit;
+        self.history_limit = history_limit;
+        self.log_interval = log_interval;
+        self.history = history;
+        self.clone()
+    }
+
+    pub fn steps_per_call(&self) -> u32 {
+        self.steps_per_call
+    }
+
+    /// Set how many generations each call to `tick_stepped` advances by, so
+    /// that an animation loop can change the speed of the simulation
+    /// without changing its frame rate. 0 is treated as 1, the default.
+    pub fn set_steps_per_call(&mut self, steps: u32) {
+        self.steps_per_call = steps.max(1);
+    }
+
+    /// Advance the universe by `steps_per_call` generations, e.g. once per
+    /// animation frame.
+    pub fn tick_stepped(&mut self) {
+        self.tick_n(self.steps_per_call);
+    }
+
+    /// Advance the universe by one generation, then insert `pattern` at
+    /// `(row, col)` like `insert_pattern` on the first call and every
+    /// `period` calls after that, e.g. to feed a stream of gliders into an
+    /// eater or a reflector without building a gun. A `period` of 0 is
+    /// treated as 1.
+    ///
+    /// The calls are counted together whatever their arguments, so a
+    /// stream should be fed by the same call every time.
+    pub fn tick_with_injection(&mut self, pattern: Pattern, row: u32, col: u32, period: u32) {
+        self.tick();
+        if self.injection_ticks.is_multiple_of(period.max(1)) {
+            self.insert_pattern(pattern, row, col);
+        }
+        self.injection_ticks = self.injection_ticks.wrapping_add(1);
+    }
+
+    pub fn undo_limit(&self) -> u32 {
+        self.undo_limit
+    }
+
+    /// Set how many ticks can be undone with `rewind`, dropping the oldest
+    /// saved states beyond that. Each one keeps a copy of the board, so this
+    /// costs memory on large boards. 0, the default, turns undo off.
+    pub fn set_undo_limit(&mut self, limit: u32) {
+        self.undo_limit = limit;
+        self.trim_undo();
+    }
+
+    fn trim_undo(&mut self) {
+        while self.undo.len() > self.undo_limit as usize {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Undo up to `n` of the most recent ticks, restoring the board, the
+    /// generation counter and the births and deaths counts to what they were
+    /// before them, and dropping their entries from the population history.
+    /// Returns how many ticks were actually undone, which is fewer than `n`
+    /// once the saved states run out, see `set_undo_limit`.
+    ///
+    /// The board is restored as it was right before the tick, so edits made
+    /// since are lost, and so is its size if it was resized or transposed
+    /// since, in which case the frozen cells, the rule region and the ant
+    /// are removed as when resizing.
+    pub fn rewind(&mut self, n: u32) -> u32 {
+        let mut rewound = 0;
+        while rewound < n {
+            let snapshot = match self.undo.pop_back() {
+                Some(snapshot) => snapshot,
+                None => break,
+            };
+            if (snapshot.width, snapshot.height) != (self.width, self.height) {
+                self.width = snapshot.width;
+                self.height = snapshot.height;
+                self.set_radius(self.radius);
+                self.rule_region = None;
+                self.frozen.clear();
+                self.ant = None;
+            }
+            self.cells = snapshot.cells;
+            self.edited = true;
+            self.generation = snapshot.generation;
+            self.births = snapshot.births;
+            self.deaths = snapshot.deaths;
+            self.history.pop_back();
+            rewound += 1;
+        }
+        rewound
+    }
+
+    /// Return a new universe one generation ahead of this one, leaving this
+    /// one unchanged.
+    pub fn peek_next(&self) -> Universe {
+        Universe {
+            cells: self.next_generation().cells,
+            ..self.clone()
+        }
+    }
+}
+
+use std::fmt;
+
+impl fmt::Display for Universe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let index = self.get_index(row, col);
+                let cell = self.cells[index];
+                let symbol = if cell { '◼' } else { '◻' };
+                write!(f, "{}", symbol)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Public methods, exported to JavaScript.
+/// This `impl` block is mostly for constructors.
+#[wasm_bindgen]
+impl Universe {
+    /// Flip the cell at `(row, column)` between alive and dead.
+    ///
+    /// Fails if the cell is outside of the universe.
+    pub fn toggle_cell(&mut self, row: u32, column: u32) -> Result<(), Error> {
+        let idx = self.checked_index(row, column)?;
+        self.cells.toggle(idx);
+        self.edited = true;
+        self.record(|| format!("toggle_cell {} {}", row, column));
+        Ok(())
+    }
+
+    // Deterministic universe with random cell states, 64 by 64.
+    pub fn hardcoded_64_by_64() -> Universe {
+        Universe::new_hardcoded(64, 64)
+    }
+
+    // Deterministic universe with random cell states, like
+    // `hardcoded_64_by_64` but of any size.
+    pub fn new_hardcoded(width: u32, height: u32) -> Universe {
+        utils::set_panic_hook();
+
+        let size = (width * height) as usize;
+        let mut cells = FixedBitSet::with_capacity(size);
+
+        for i in 0..size {
+            cells.set(i, i % 2 == 0 || i % 7 == 0);
+        }
+
+        Universe::from_bitset(width, height, cells)
+    }
+
+    // Create a universe with a Gosper glider gun in its top-left corner and an
+    // eater placed across the gun's glider stream, which destroys every
+    // glider it is hit by and then repairs itself.
+    //
+    // This is a dynamic equilibrium: from generation 31 on, the whole board
+    // repeats every 30 generations, and at every multiple of 30 its
+    // population is `GUN_AND_EATER_POPULATION` (49).
+    // The universe must be at least 40 cells wide and 26 cells tall.
+    pub fn new_gun_and_eater(width: u32, height: u32) -> Result<Universe, Error> {
+        if width < 40 || height < 26 {
+            return Err(Error::TooSmall {
+                width,
+                height,
+                min_width: 40,
+                min_height: 26,
+            });
+        }
+
+        let mut universe = Universe::from_cells(width, height, &[]);
+        universe.insert_pattern(Pattern::GosperGliderGun, 1, 1);
+        universe.insert_pattern(Pattern::Eater1, 20, 34);
+        Ok(universe)
+    }
+
+    // Create a universe with a random initial position, in a non-deterministic
+    // manner unlike the method above.
+    // The grid's dimensions are passed as an argument.
+    pub fn new(width : u32, height : u32) -> Universe {
+        utils::set_panic_hook();
+
+        let size = (width * height) as usize;
+        let mut cells = FixedBitSet::with_capacity(size);
+        for i in 0 .. size {
+                if utils::random() < 0.5 {
+                    cells.set(i, true);
+                } else {
+                    cells.set(i, false);
+                }
+            };
+
+        /*
+        Exercise 2 of the debugging chapter.
+        Uncomment this, comment the marked line in Cargo.toml, and run `wasm-pack build --release`.
+
+        panic!("Just for testing purposes");
+        */
+
+        Universe::from_bitset(width, height, cells)
+    }
+
+    // Create a universe with a random initial position like `new`, but drawn
+    // from a pseudo-random generator seeded with `seed`, so that the same
+    // seed always gives the same universe.
+    pub fn new_seeded(width : u32, height : u32, seed : u64) -> Universe {
+        utils::set_panic_hook();
+
+        let mut rng = utils::Rng::new(seed);
+        let size = (width * height) as usize;
+        let mut cells = FixedBitSet::with_capacity(size);
+        for i in 0 .. size {
+            cells.set(i, rng.next_f64() < 0.5);
+        }
+
+        Universe::from_bitset(width, height, cells)
+    }
+
+    /// Number of live cells in the universe.
+    pub fn population(&self) -> u32 {
+        self.cells.count_ones(.. (self.width * self.height) as usize) as u32
+    }
+
+    /// For every cell in row-major order, 0 if it is dead, 1 if it is alive
+    /// with at least one dead neighbour, on the outline of its live region,
+    /// and 2 if all of its neighbours are alive, inside the region, e.g. to
+    /// draw the outline of large blobs apart from their fill. Under a dead
+    /// boundary, live cells on the edges are on the outline.
+    pub fn outline(&self) -> Vec<u8> {
+        let neighbors = (self.row_offsets.len() * self.col_offsets.len()).saturating_sub(1);
+        let mut outline = Vec::with_capacity((self.width * self.height) as usize);
+        for row in 0..self.height {
+            for col in 0..self.width {
+                outline.push(if !self.cells[self.get_index(row, col)] {
+                    0
+                } else if (self.live_neighbor_count(row, col) as usize) < neighbors {
+                    1
+                } else {
+                    2
+                });
+            }
+        }
+        outline
+    }
+
+    /// The highest number of live neighbours of any cell, dead or alive,
+    /// which shows how crowded the densest spot of the board is, or 0 for
+    /// an empty universe.
+    pub fn max_neighbor_count(&self) -> u8 {
+        let mut max = 0;
+        for row in 0..self.height {
+            for col in 0..self.width {
+                max = max.max(self.live_neighbor_count(row, col));
+            }
+        }
+        max
+    }
+
+    /// Kill every live cell without any live neighbour, such as the lone
+    /// cells left behind by a chaotic run, without ticking, and return how
+    /// many there were. Unless the rule lets cells survive with no
+    /// neighbours (S0), they would all die during the next tick anyway.
+    pub fn remove_single_cells(&mut self) -> u32 {
+        let single: Vec<usize> = self
+            .live_cells()
+            .into_iter()
+            .filter(|&(row, col)| self.live_neighbor_count(row, col) == 0)
+            .map(|(row, col)| self.get_index(row, col))
+            .collect();
+        for &idx in &single {
+            self.cells.set(idx, false);
+        }
+        self.edited |= !single.is_empty();
+        single.len() as u32
+    }
+
+    /// Number of live cells the next generation will have, without building
+    /// it, e.g. to time the rule evaluation apart from the cost of copying
+    /// the board in `tick`.
+    pub fn count_next_live(&self) -> u32 {
+        let mut count = 0;
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                if self.next_cell_state_at(idx, self.cells[idx], self.live_neighbor_count(row, col)) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Indices into `cells` of the cells that the next tick will flip, in
+    /// increasing order, without ticking, e.g. to mark the cells about to
+    /// change. This is what `tick_delta` will return.
+    pub fn pending_changes(&self) -> Vec<u32> {
+        let mut changes = Vec::new();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                let cell = self.cells[idx];
+                if self.next_cell_state_at(idx, cell, self.live_neighbor_count(row, col)) != cell {
+                    changes.push(idx as u32);
+                }
+            }
+        }
+        changes
+    }
+
+    pub fn clear_all_cells(&mut self) {
+        for i in 0 .. (self.width() * self.height()) as usize {
+            self.cells.set(i, false)
+        };
+        self.edited = true;
+
+        self.peak_population = 0;
+        self.peak_generation = self.generation;
+        self.escaped = 0;
+        self.record(|| "clear_all_cells".to_string());
+    }
+
+    // Reinitialize an already existing universe with a random state.
+    pub fn reinitialize_rng(&mut self) {
+        let size = (self.width() * self.height()) as usize;
+
+        for i in 0 .. size {
+            if utils::random() < 0.5 {
+                self.cells.set(i, true);
+            } else {
+                self.cells.set(i, false);
+            }
+        };
+        self.edited = true;
+    }
+
+    /// Bring each dead cell to life with probability `density`, clamped to
+    /// `[0, 1]`, leaving live cells alive, e.g. to perturb a stable pattern
+    /// without destroying it.
+    ///
+    /// With a `seed` the same cells are picked every time on the same
+    /// board; without one they are drawn from `Math.random()`, or the
+    /// generator set with `use_seeded_rng`.
+    pub fn seed_dead_cells(&mut self, density: f64, seed: Option<u64>) {
+        let density = density.clamp(0.0, 1.0);
+        let mut rng = seed.map(utils::Rng::new);
+        let mut random = || match rng.as_mut() {
+            Some(rng) => rng.next_f64(),
+            None => utils::random(),
+        };
+
+        for i in 0 .. (self.width * self.height) as usize {
+            if !self.cells[i] && random() < density {
+                self.cells.insert(i);
+                self.edited = true;
+            }
+        }
+    }
+
+    /// Flip each cell between alive and dead with its own probability, given
+    /// by `weights` for every cell in row-major order, e.g. to perturb the
+    /// board more near its center than near its edges. The same `seed`
+    /// flips the same cells every time on the same board.
+    ///
+    /// Weights of 0 or less never flip a cell, and weights of 1 or more
+    /// always do. Fails unless `weights` holds exactly one value per cell.
+    pub fn mutate_weighted(&mut self, weights: &[f32], seed: u64) -> Result<(), Error> {
+        let size = (self.width * self.height) as usize;
+        if weights.len() != size {
+            return Err(Error::WrongLength {
+                what: "weights",
+                expected: size as u64,
+                found: weights.len() as u64,
+            });
+        }
+
+        let mut rng = utils::Rng::new(seed);
+        for (i, &weight) in weights.iter().enumerate() {
+            if rng.next_f64() < weight as f64 {
+                self.cells.toggle(i);
+                self.edited = true;
+            }
+        }
+        Ok(())
+    }
+
+    // Given an array of `u8`s representting the cells' neighbourhood,
+    // set that cell's neighbours to the values in the array.
+    // Because an array or vector of `bool`s do not implement the `FromWasmAbi`
+    // trait, we must instead use `u8`s.
+    // Fails unless `neighbourhood` holds exactly 9 values.
+    pub fn set_pattern_at(&mut self, row: u32, column: u32, neighbourhood : &[u8]) -> Result<(), Error> {
+        let neighbourhood = neighbourhood.try_into().map_err(|_| Error::WrongLength {
+            what: "cells",
+            expected: 9,
+            found: neighbourhood.len() as u64,
+        })?;
+        self.write_pattern_at(row, column, neighbourhood);
+        Ok(())
+    }
+
+    // `set_pattern_at` for neighbourhoods already known to have the right
+    // size.
+    fn write_pattern_at(&mut self, row: u32, column: u32, neighbourhood : &[u8; 9]) {
+        let mut i = 0;
+
+        for delta_row in [self.height - 1, 0, 1].iter().cloned() {
+            for delta_col in [self.width - 1, 0, 1].iter().cloned() {
+                let neighbor_row = (row + delta_row) % self.height;
+                let neighbor_col = (column + delta_col) % self.width;
+                let idx = self.get_index(neighbor_row, neighbor_col);
+                let cell = neighbourhood[i] > 0;
+                self.cells.set(idx, cell);
+                i += 1;
+            }
+        }
+        self.edited = true;
+    }
+
+    // Like `set_pattern_at`, but only turns on the cells marked as alive in
+    // `neighbourhood`; cells marked as dead are left as they were.
+    fn add_pattern_at(&mut self, row: u32, column: u32, neighbourhood : &[u8; 9]) {
+        let mut i = 0;
+
+        for delta_row in [self.height - 1, 0, 1].iter().cloned() {
+            for delta_col in [self.width - 1, 0, 1].iter().cloned() {
+                if neighbourhood[i] > 0 {
+                    let neighbor_row = (row + delta_row) % self.height;
+                    let neighbor_col = (column + delta_col) % self.width;
+                    let idx = self.get_index(neighbor_row, neighbor_col);
+                    self.cells.set(idx, true);
+                }
+                i += 1;
+            }
+        }
+        self.edited = true;
+    }
+
+    // Create one instance of Gosper's glider at the center of
     // the board.
+    //
+    // The whole 3x3 neighbourhood around `(row, column)` is overwritten, so
+    // any live cells there that are not part of the glider are killed.
+    // Use `add_glider_at` to place a glider over existing content.
     pub fn new_glider_at(&mut self, row: u32, column: u32) {
+        self.write_pattern_at(row, column, &GLIDER);
+    }
+
+    // Add a glider centered on `(row, column)`, in the same orientation as
+    // `new_glider_at`.
+    //
+    // Unlike `new_glider_at`, only the glider's live cells are turned on:
+    // every other cell in the 3x3 neighbourhood keeps its current state.
+    pub fn add_glider_at(&mut self, row: u32, column: u32) {
+        self.add_pattern_at(row, column, &GLIDER);
+    }
+
+    // Create a glider centered on `(row, column)`, heading in one of the four
+    // diagonal directions, overwriting its 3x3 neighbourhood like
+    // `new_glider_at`.
+    //
+    // `orientation` is taken modulo 4:
+    // 0 - heading up and to the right (the same glider as `new_glider_at`),
+    // 1 - heading down and to the right,
+    // 2 - heading down and to the left,
+    // 3 - heading up and to the left.
+    pub fn new_glider_oriented(&mut self, row: u32, column: u32, orientation: u8) {
+        let mut neighbourhood = GLIDER;
+
+        // Each quarter turn clockwise rotates the glider's heading with it.
+        for _ in 0..orientation % 4 {
+            let previous = neighbourhood;
+            for r in 0..3 {
+                for c in 0..3 {
+                    neighbourhood[r * 3 + c] = previous[(2 - c) * 3 + r];
+                }
+            }
+        }
+
+        self.write_pattern_at(row, column, &neighbourhood);
+    }
+
+    fn new_traffic_light_at(&mut self, row: u32, column: u32) {
         let neighbourhood = [
-                0, 1, 1,
-                1, 0, 1,
-                0, 0, 1,
-            ];
+            1, 1, 1,
+            1, 0, 1,
+            1, 1, 1,
+        ];
+
+        self.write_pattern_at(row, column, &neighbourhood);
+    }
+
+    pub fn new_pre_pulsar_at(&mut self, row: u32, column: u32) {
+        self.new_traffic_light_at(row, column + self.width() - 3);
+        self.new_traffic_light_at(row, column + 3);
+    }
+
+    /// Shortest Euclidean distance between the cells `(r1, c1)` and
+    /// `(r2, c2)`, going across the edges of the universe when that is
+    /// shorter since it wraps around. An axis of a universe without any rows
+    /// or columns adds nothing to it.
+    pub fn toroidal_distance(&self, r1: u32, c1: u32, r2: u32, c2: u32) -> f64 {
+        let wrapped = |a: u32, b: u32, size: u32| {
+            if size == 0 {
+                return 0.0;
+            }
+            let direct = (a % size).max(b % size) - (a % size).min(b % size);
+            direct.min(size - direct) as f64
+        };
+
+        let rows = wrapped(r1, r2, self.height);
+        let cols = wrapped(c1, c2, self.width);
+        (rows * rows + cols * cols).sqrt()
+    }
+
+    /// Copy the `height` by `width` rectangle whose top-left cell is
+    /// `(row, col)` into a new universe with the same rule.
+    ///
+    /// The rectangle does not wrap around: it is clipped to the bounds of
+    /// this universe, so the result may be smaller than requested.
+    pub fn subgrid(&self, row: u32, col: u32, height: u32, width: u32) -> Universe {
+        let row = row.min(self.height);
+        let col = col.min(self.width);
+        let height = height.min(self.height - row);
+        let width = width.min(self.width - col);
+
+        let mut cells = FixedBitSet::with_capacity((width * height) as usize);
+        for r in 0..height {
+            for c in 0..width {
+                let idx = self.get_index(row + r, col + c);
+                cells.set((r * width + c) as usize, self.cells[idx]);
+            }
+        }
+
+        let mut universe = Universe::from_bitset(width, height, cells);
+        universe.birth = self.birth;
+        universe.survival = self.survival;
+        universe.set_radius(self.radius);
+        universe
+    }
+
+    /// Build a `width` by `height` universe, with the same rule as
+    /// `quadrant`, whose top-left quadrant is a copy of `quadrant` and whose
+    /// other quadrants are its mirror images, so that the board is
+    /// symmetric across its middle row and column, and stays so as it
+    /// evolves.
+    ///
+    /// With an odd width or height, the middle column or row is shared by
+    /// the quadrants on either side of it. Cells of `quadrant` that do not
+    /// fit in the top-left quadrant are dropped.
+    pub fn new_symmetric(width: u32, height: u32, quadrant: &Universe) -> Universe {
+        let mut cells = FixedBitSet::with_capacity((width * height) as usize);
+        for (row, col) in quadrant.live_cells() {
+            if row >= height.div_ceil(2) || col >= width.div_ceil(2) {
+                continue;
+            }
+            for &r in &[row, height - 1 - row] {
+                for &c in &[col, width - 1 - col] {
+                    cells.insert((r * width + c) as usize);
+                }
+            }
+        }
+
+        let mut universe = Universe::from_bitset(width, height, cells);
+        universe.birth = quadrant.birth;
+        universe.survival = quadrant.survival;
+        universe.set_radius(quadrant.radius);
+        universe
+    }
+
+    /// Build a universe with `left` and `right` side by side, following the
+    /// rule of `left`, e.g. to watch chaos on one side meet order on the
+    /// other.
+    ///
+    /// Fails unless both universes have the same height.
+    pub fn from_halves(left: &Universe, right: &Universe) -> Result<Universe, Error> {
+        if left.height != right.height {
+            return Err(Error::DimensionMismatch {
+                width: left.width,
+                height: left.height,
+                other_width: right.width,
+                other_height: right.height,
+            });
+        }
+        Ok(Universe::joined(left, right, left.width + right.width, left.height, (0, left.width)))
+    }
+
+    /// Build a universe with `top` above `bottom`, following the rule of
+    /// `top`, like `from_halves` but vertically.
+    ///
+    /// Fails unless both universes have the same width.
+    pub fn from_stacked_halves(top: &Universe, bottom: &Universe) -> Result<Universe, Error> {
+        if top.width != bottom.width {
+            return Err(Error::DimensionMismatch {
+                width: top.width,
+                height: top.height,
+                other_width: bottom.width,
+                other_height: bottom.height,
+            });
+        }
+        Ok(Universe::joined(top, bottom, top.width, top.height + bottom.height, (top.height, 0)))
+    }
+
+    // A `width` by `height` universe with the rule of `first`, holding the
+    // cells of `first` at its top-left corner and those of `second` at
+    // `offset`, as `(row, column)`.
+    fn joined(first: &Universe, second: &Universe, width: u32, height: u32, offset: (u32, u32)) -> Universe {
+        let mut universe = Universe::from_bitset(width, height, FixedBitSet::with_capacity((width * height) as usize));
+        universe.birth = first.birth;
+        universe.survival = first.survival;
+        universe.set_radius(first.radius);
+        universe.overlay(first, 0, 0);
+        universe.overlay(second, offset.0, offset.1);
+        universe
+    }
+
+    /// Insert one of the library's patterns with its top-left corner at
+    /// `(row, column)`.
+    ///
+    /// Only the pattern's live cells are turned on, and cells that would fall
+    /// outside of the universe are dropped.
+    pub fn insert_pattern(&mut self, pattern: Pattern, row: u32, column: u32) {
+        for (r, c) in pattern.cells().iter().cloned() {
+            if let Some(idx) = self.try_get_index_offset(row, r, column, c) {
+                self.cells.set(idx, true);
+            }
+        }
+        self.edited = true;
+        self.record(|| format!("insert_pattern {:?} {} {}", pattern, row, column));
+    }
+
+    /// Like `insert_pattern`, but with every cell of the pattern drawn as a
+    /// `scale` by `scale` block of live cells, clipped to the universe, e.g.
+    /// for chunky pixel art. A `scale` of 0 is treated as 1.
+    ///
+    /// A scaled pattern is a different pattern entirely, which evolves in its
+    /// own way: a glider scaled by 2 is not a larger glider.
+    pub fn insert_pattern_scaled(&mut self, pattern: Pattern, row: u32, column: u32, scale: u32) {
+        let scale = scale.max(1);
+        for (r, c) in pattern.cells().iter().cloned() {
+            let top = row.saturating_add(r.saturating_mul(scale));
+            let left = column.saturating_add(c.saturating_mul(scale));
+            self.fill_rect(top, left, scale, scale, true);
+        }
+    }
+
+    /// Like `insert_pattern`, but cells that would fall past an edge wrap
+    /// around to the opposite edge instead of being dropped, as they would
+    /// when the pattern moves there, e.g. to place it across the seam on
+    /// purpose.
+    pub fn insert_pattern_wrapped(&mut self, pattern: Pattern, row: u32, column: u32) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        for (r, c) in pattern.cells().iter().cloned() {
+            let r = (row as u64 + r as u64) % self.height as u64;
+            let c = (column as u64 + c as u64) % self.width as u64;
+            let idx = self.get_index(r as u32, c as u32);
+            self.cells.set(idx, true);
+        }
+        self.edited = true;
+        self.record(|| format!("insert_pattern_wrapped {:?} {} {}", pattern, row, column));
+    }
+
+    /// Copy the live cells of `other` onto this universe, with its top-left
+    /// corner at `(row, column)`.
+    ///
+    /// Cells that are already alive stay alive, and cells of `other` that
+    /// would fall outside of this universe are dropped.
+    pub fn overlay(&mut self, other: &Universe, row: u32, column: u32) {
+        for (r, c) in other.live_cells() {
+            if let Some(idx) = self.try_get_index_offset(row, r, column, c) {
+                self.cells.set(idx, true);
+            }
+        }
+        self.edited = true;
+    }
+
+    /// A new `new_width` by `new_height` universe, with the same rule, in
+    /// which the board of this one is copied with its top-left corner at
+    /// `(row, col)` and every other cell is dead, e.g. to give a pattern room
+    /// to run without changing it. This universe is left unchanged.
+    ///
+    /// Fails if the board does not fit in the new universe at that offset.
+    pub fn embed_in(&self, new_width: u32, new_height: u32, row: u32, col: u32) -> Result<Universe, Error> {
+        let min_width = col as u64 + self.width as u64;
+        let min_height = row as u64 + self.height as u64;
+        if min_width > new_width as u64 || min_height > new_height as u64 {
+            return Err(Error::TooSmall {
+                width: new_width,
+                height: new_height,
+                min_width: min_width.min(u32::MAX as u64) as u32,
+                min_height: min_height.min(u32::MAX as u64) as u32,
+            });
+        }
+
+        let size = (new_width * new_height) as usize;
+        let mut universe = Universe::from_bitset(new_width, new_height, FixedBitSet::with_capacity(size));
+        universe.birth = self.birth;
+        universe.survival = self.survival;
+        universe.set_radius(self.radius);
+        universe.overlay(self, row, col);
+        Ok(universe)
+    }
+
+    /// Overlay copies of `pattern` all over the universe, one every
+    /// `spacing_r` rows and `spacing_c` columns starting from the top-left
+    /// corner, e.g. to fill the board with blocks or gliders.
+    ///
+    /// Copies are clipped at the right and bottom edges. A spacing of 0 is
+    /// treated as 1.
+    pub fn tile_pattern(&mut self, pattern: &Universe, spacing_r: u32, spacing_c: u32) {
+        let spacing_r = spacing_r.max(1) as usize;
+        let spacing_c = spacing_c.max(1) as usize;
+        for row in (0..self.height).step_by(spacing_r) {
+            for column in (0..self.width).step_by(spacing_c) {
+                self.overlay(pattern, row, column);
+            }
+        }
+    }
+
+    /// Insert up to `count` copies of `pattern` at random positions, drawn
+    /// from `seed` so that the same seed scatters them the same way on the
+    /// same board, e.g. to fill a board with gliders for a demo. Returns the
+    /// number of copies inserted.
+    ///
+    /// Each copy lies entirely within the universe, and neither overlaps nor
+    /// touches a live cell, including those of the other copies, so that it
+    /// starts out as it is in the library. When no room is found for a copy
+    /// after a hundred tries, the remaining ones are left out.
+    pub fn scatter_pattern(&mut self, pattern: Pattern, count: u32, seed: u64) -> u32 {
+        let mut rng = utils::Rng::new(seed);
+        let mut placed = 0;
+        while placed < count && self.place_somewhere(pattern, &mut rng) {
+            placed += 1;
+        }
+        placed
+    }
+
+    /// Insert `pattern` at a random position drawn from `seed`, like a
+    /// single copy of `scatter_pattern`, e.g. to add a glider somewhere.
+    /// Returns whether room was found for it.
+    pub fn place_random(&mut self, pattern: Pattern, seed: u64) -> bool {
+        self.place_somewhere(pattern, &mut utils::Rng::new(seed))
+    }
+
+    // Insert `pattern` at a position drawn from `rng` where it neither
+    // overlaps nor touches a live cell, trying `SCATTER_ATTEMPTS` of them
+    // before giving up. Returns whether it was inserted.
+    fn place_somewhere(&mut self, pattern: Pattern, rng: &mut utils::Rng) -> bool {
+        let (height, width) = pattern.dimensions();
+        if height > self.height || width > self.width {
+            return false;
+        }
+
+        for _ in 0..SCATTER_ATTEMPTS {
+            let row = (rng.next_u64() % (self.height - height + 1) as u64) as u32;
+            let column = (rng.next_u64() % (self.width - width + 1) as u64) as u32;
+
+            // The pattern's bounding box and the ring of cells around it.
+            let mut rows = row.saturating_sub(1)..(row + height + 1).min(self.height);
+            let columns = column.saturating_sub(1)..(column + width + 1).min(self.width);
+            let free = rows.all(|r| columns.clone().all(|c| !self.cells[self.get_index(r, c)]));
+            if free {
+                self.insert_pattern(pattern, row, column);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Make every cell of the `height` by `width` rectangle whose top-left
+    /// cell is `(row, col)` alive or dead, clipped to the universe.
+    #[wasm_bindgen(js_name = fill_rect)]
+    pub fn fill_rect_js(&mut self, row: u32, col: u32, height: u32, width: u32, alive: bool) {
+        self.fill_rect(row, col, height, width, alive);
+    }
+
+    /// Make every cell on the edges of the universe, its first and last rows
+    /// and columns, alive or dead, e.g. to frame the board, or to wall it
+    /// when frozen with `freeze_cell`.
+    pub fn set_border(&mut self, alive: bool) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let (width, height) = (self.width, self.height);
+        self.fill_rect(0, 0, 1, width, alive)
+            .fill_rect(height - 1, 0, 1, width, alive)
+            .fill_rect(0, 0, height, 1, alive)
+            .fill_rect(0, width - 1, height, 1, alive);
+    }
+
+    /// Flip every cell of the `height` by `width` rectangle whose top-left
+    /// cell is `(row, col)`, clipped to the universe.
+    #[wasm_bindgen(js_name = toggle_rect)]
+    pub fn toggle_rect_js(&mut self, row: u32, col: u32, height: u32, width: u32) {
+        self.toggle_rect(row, col, height, width);
+    }
+
+    /// Bring to life the cells on the straight line from `(r0, c0)` to
+    /// `(r1, c1)`.
+    #[wasm_bindgen(js_name = draw_line)]
+    pub fn draw_line_js(&mut self, r0: u32, c0: u32, r1: u32, c1: u32) {
+        self.draw_line(r0, c0, r1, c1);
+    }
+
+    /// Whether `pattern` fits entirely within the universe with its top-left
+    /// corner at `(row, column)`, so that `insert_pattern` keeps all of its
+    /// cells.
+    pub fn pattern_fits(&self, pattern: Pattern, row: u32, column: u32) -> bool {
+        let (rows, cols) = pattern.dimensions();
+        row as u64 + rows as u64 <= self.height as u64 && column as u64 + cols as u64 <= self.width as u64
+    }
+
+    /// Names of all the patterns that can be inserted with `insert_pattern`,
+    /// in the order of the `Pattern` enum.
+    pub fn pattern_names() -> Vec<String> {
+        patterns::PATTERNS
+            .iter()
+            .map(|info| info.name.to_string())
+            .collect()
+    }
+
+    /// Whether any live cell lies on the outermost ring of the universe.
+    ///
+    /// Past that point a pattern starts interacting with itself across the
+    /// edges, since the universe wraps around; giving it more room, e.g. with
+    /// a larger universe, avoids that.
+    pub fn touches_edge(&self) -> bool {
+        if self.width == 0 || self.height == 0 {
+            return false;
+        }
+
+        let last_row = self.height - 1;
+        let last_col = self.width - 1;
+        (0..self.width).any(|col| {
+            self.cells[self.get_index(0, col)] || self.cells[self.get_index(last_row, col)]
+        }) || (0..self.height).any(|row| {
+            self.cells[self.get_index(row, 0)] || self.cells[self.get_index(row, last_col)]
+        })
+    }
+
+    /// Reduced-resolution view of the universe, for minimaps of large boards.
+    ///
+    /// Each output cell holds the number of live cells in the corresponding
+    /// `factor` by `factor` block (saturating at 255), in row-major order.
+    /// The output is `ceil(width / factor)` cells wide and
+    /// `ceil(height / factor)` cells tall: blocks along the right and bottom
+    /// edges are smaller when the dimensions are not multiples of `factor`.
+    /// A `factor` of 0 is treated as 1.
+    pub fn downsample(&self, factor: u32) -> Vec<u8> {
+        let factor = factor.max(1);
+        let out_width = self.width.div_ceil(factor);
+        let out_height = self.height.div_ceil(factor);
+
+        let mut out = vec![0u8; (out_width * out_height) as usize];
+        for (row, col) in self.live_cells() {
+            let i = ((row / factor) * out_width + col / factor) as usize;
+            out[i] = out[i].saturating_add(1);
+        }
+        out
+    }
+
+    /// State of every cell as one byte, 1 if alive and 0 if dead, in
+    /// row-major order.
+    pub fn cell_bytes(&self) -> Vec<u8> {
+        (0 .. (self.width * self.height) as usize)
+            .map(|i| self.cells[i] as u8)
+            .collect()
+    }
+
+    /// The board as an array of rows, each an array of 1 for live cells and
+    /// 0 for dead ones, i.e. a `number[][]` indexed by row then column.
+    ///
+    /// This builds a JavaScript array per row, which is convenient for
+    /// prototyping but much slower than reading the buffer behind `cells`,
+    /// so it is meant for small boards.
+    pub fn to_grid(&self) -> JsValue {
+        let grid = js_sys::Array::new_with_length(self.height);
+        for row in 0..self.height {
+            let cells = js_sys::Array::new_with_length(self.width);
+            for col in 0..self.width {
+                let cell = self.cells[self.get_index(row, col)];
+                cells.set(col, JsValue::from(cell as u8));
+            }
+            grid.set(row, cells.into());
+        }
+        grid.into()
+    }
+
+    /// The board as an object `{ width, height, live }`, where `live` is a
+    /// `Uint32Array` of the index in `cells` of every live cell, in
+    /// increasing order.
+    ///
+    /// That is 4 bytes per live cell instead of one bit per cell, so it is
+    /// less to copy than the buffer behind `cells` when fewer than one cell
+    /// in 32 is alive, and lets a renderer draw only those cells. On denser
+    /// boards, reading the buffer is cheaper.
+    pub fn sparse_snapshot(&self) -> JsValue {
+        let live: Vec<u32> = self
+            .cells
+            .ones()
+            .take_while(|&i| i < (self.width * self.height) as usize)
+            .map(|i| i as u32)
+            .collect();
+
+        let snapshot = js_sys::Object::new();
+        for (key, value) in [
+            ("width", JsValue::from(self.width)),
+            ("height", JsValue::from(self.height)),
+            ("live", js_sys::Uint32Array::from(&live[..]).into()),
+        ] {
+            js_sys::Reflect::set(&snapshot, &JsValue::from_str(key), &value)
+                .expect("setting a property of a new object cannot fail");
+        }
+        snapshot.into()
+    }
+
+    /// Advance the universe by `generations` ticks, returning the board after
+    /// each of them.
+    ///
+    /// The result is the `cell_bytes` of each generation one after the other,
+    /// so frame `n` starts at byte `n * width * height`. That is one byte per
+    /// cell per generation, so to bound memory use the number of generations
+    /// is reduced as needed to keep the result within `MAX_TRAJECTORY_BYTES`
+    /// (64 MiB); the universe is only advanced by the generations that were
+    /// actually returned.
+    pub fn trajectory(&mut self, generations: u32) -> Vec<u8> {
+        let size = (self.width * self.height) as usize;
+        let generations = match MAX_TRAJECTORY_BYTES.checked_div(size) {
+            Some(max) => generations.min(max as u32),
+            None => generations,
+        };
+
+        let mut frames = Vec::with_capacity(size * generations as usize);
+        for _ in 0..generations {
+            self.tick();
+            frames.extend(self.cell_bytes());
+        }
+        frames
+    }
+
+    /// Reflect the universe across its main diagonal, so that the cell at
+    /// `(row, column)` moves to `(column, row)`.
+    ///
+    /// Non-square universes have their width and height swapped.
+    pub fn transpose(&mut self) {
+        let mut cells = FixedBitSet::with_capacity((self.width * self.height) as usize);
+        for (row, col) in self.live_cells() {
+            cells.insert((col * self.height + row) as usize);
+        }
+
+        std::mem::swap(&mut self.width, &mut self.height);
+        std::mem::swap(&mut self.row_offsets, &mut self.col_offsets);
+        self.cells = cells;
+        self.edited = true;
+        self.rule_region = None;
+        self.frozen.clear();
+        self.ant = None;
+    }
+
+    /// 64-bit FNV-1a hash of the universe's dimensions and cells.
+    ///
+    /// Equal boards always have equal hashes, so this is a cheap way to
+    /// detect repeated states; different boards can collide, so a match
+    /// should be confirmed by comparing the cells.
+    pub fn state_hash(&self) -> u64 {
+        let mut hash = FNV_OFFSET;
+        hash = fnv1a(hash, &self.width.to_le_bytes());
+        hash = fnv1a(hash, &self.height.to_le_bytes());
+        for word in self.logical_words() {
+            hash = fnv1a(hash, &word.to_le_bytes());
+        }
+        hash
+    }
+
+    /// Smallest number of ticks, up to `max_period`, after which the board
+    /// is back to its current state, or `None` if it does not come back
+    /// within that many ticks.
+    ///
+    /// The universe is restored to its current state afterwards.
+    pub fn detect_period(&mut self, max_period: u32) -> Option<u32> {
+        let snapshot = self.clone();
+        let hash = self.state_hash();
+
+        let mut period = None;
+        for ticks in 1..=max_period {
+            self.tick();
+            if self.state_hash() == hash && self.same_cells(&snapshot) {
+                period = Some(ticks);
+                break;
+            }
+        }
+
+        *self = snapshot;
+        period
+    }
+
+    /// Run a soup, the basic step of the searches for rare patterns among
+    /// random ones, and return a signature of what it left, as 16
+    /// hexadecimal digits.
+    ///
+    /// The board is cleared, then the `SOUP_SIZE` by `SOUP_SIZE` square in
+    /// its middle is filled with random cells, each alive with probability
+    /// one half, drawn from `seed`. The universe then ticks until it is
+    /// back to a state it was already in, like `tick_until_repeat`, at most
+    /// `max_generations` times. The signature is a hash of the
+    /// `canonical_form` of each group of connected live cells left, which
+    /// does not depend on where they are, their orientation or their order,
+    /// so soups leaving the same objects get the same signature. The same
+    /// seed always gives the same soup, so the same signature on a board of
+    /// the same size and rule.
+    ///
+    /// Fails, leaving the universe unchanged, if it is smaller than the
+    /// soup.
+    pub fn soup_result(&mut self, seed: u64, max_generations: u32) -> Result<String, Error> {
+        if self.width < SOUP_SIZE || self.height < SOUP_SIZE {
+            return Err(Error::TooSmall {
+                width: self.width,
+                height: self.height,
+                min_width: SOUP_SIZE,
+                min_height: SOUP_SIZE,
+            });
+        }
+
+        self.clear_all_cells();
+        let mut rng = utils::Rng::new(seed);
+        let (top, left) = ((self.height - SOUP_SIZE) / 2, (self.width - SOUP_SIZE) / 2);
+        for row in top..top + SOUP_SIZE {
+            for col in left..left + SOUP_SIZE {
+                let idx = self.get_index(row, col);
+                self.cells.set(idx, rng.next_f64() < 0.5);
+            }
+        }
+        self.tick_until_repeat(max_generations);
+        Ok(self.ash_signature())
+    }
+
+    // The signature of `soup_result` for whatever is on the board.
+    fn ash_signature(&self) -> String {
+        let mut objects: Vec<u64> = self
+            .clusters()
+            .iter()
+            .map(|cluster| Universe::from_shape(&canonical_shape(cluster)).state_hash())
+            .collect();
+        objects.sort_unstable();
+        let hash = objects.iter().fold(FNV_OFFSET, |hash, object| fnv1a(hash, &object.to_le_bytes()));
+        format!("{:016x}", hash)
+    }
+
+    /// Whether the whole board comes back to its current state within
+    /// `max_period` ticks, i.e. it only holds still lifes and oscillators
+    /// whose periods divide a common period up to `max_period`, and nothing
+    /// that moves or grows.
+    ///
+    /// The universe is left exactly as it was, generation counter and
+    /// history included, see `detect_period`. Since the universe wraps
+    /// around, a spaceship eventually comes back too, e.g. a glider after
+    /// `4 * width` ticks on a square universe, so `max_period` should stay
+    /// below that to tell them apart.
+    pub fn is_periodic(&mut self, max_period: u32) -> bool {
+        self.detect_period(max_period).is_some()
+    }
+
+    /// Tick until the board is back to a state it was already in, at most
+    /// `max_generations` times, returning the generation at which that
+    /// happened, or `None` if no state repeated within the limit.
+    ///
+    /// This catches still lifes and oscillators as well as longer cycles.
+    /// States are compared by `state_hash`, so a hash collision could in
+    /// principle end the search early. To bound memory use, at most
+    /// `MAX_REPEAT_STATES` hashes are remembered; states after that are
+    /// only checked against the ones already recorded.
+    pub fn tick_until_repeat(&mut self, max_generations: u32) -> Option<u32> {
+        let mut seen = HashSet::new();
+        seen.insert(self.state_hash());
+
+        for _ in 0..max_generations {
+            self.tick();
+            let hash = self.state_hash();
+            if seen.contains(&hash) {
+                return Some(self.generation);
+            }
+            if seen.len() < MAX_REPEAT_STATES {
+                seen.insert(hash);
+            }
+        }
+        None
+    }
+
+    /// Tick until the board settles into still lifes and oscillators, at
+    /// most `max_generations` times, e.g. to let a soup stabilise before
+    /// looking at what it left, and return how many ticks that took. The
+    /// settled board is left in place.
+    ///
+    /// The board is taken to have settled once it is back to a state it was
+    /// already in, as in `tick_until_repeat`, so the count includes the
+    /// ticks of one full period: a blinker takes 2. A board that never
+    /// repeats within the limit, like one still growing, ticks
+    /// `max_generations` times and that is what is returned. Since the
+    /// universe wraps around, spaceships eventually repeat too.
+    pub fn settle(&mut self, max_generations: u32) -> u32 {
+        let start = self.generation;
+        match self.tick_until_repeat(max_generations) {
+            Some(generation) => generation.wrapping_sub(start),
+            None => max_generations,
+        }
+    }
+
+    /// Tick until the cell at `(row, column)` is dead, at most
+    /// `max_generations` times, returning the generation at which it
+    /// first was, or `None` if it stayed alive within the limit, e.g. to see
+    /// how long a spot lasts under a gun's fire. If the cell is already
+    /// dead, this returns the current generation without ticking.
+    ///
+    /// The universe is left advanced. Fails, without ticking, if the cell
+    /// is outside of the universe.
+    pub fn ticks_until_cell_dead(&mut self, row: u32, column: u32, max_generations: u32) -> Result<Option<u32>, Error> {
+        if !self.get_cell(row, column)? {
+            return Ok(Some(self.generation));
+        }
+
+        for _ in 0..max_generations {
+            self.tick();
+            if !self.get_cell(row, column)? {
+                return Ok(Some(self.generation));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Tick until the board repeats, at most `max_generations` times, and
+    /// tell what it settled into: `still-life`, `oscillator(period=N)`,
+    /// `spaceship(period=N)` when it repeats shifted elsewhere rather than
+    /// in place, or `unknown/growing` when nothing repeated within the
+    /// limit. The universe is left advanced.
+    ///
+    /// The repeat found is the first one, which is how long it takes for
+    /// whatever is on the board to come back as a whole: on a board holding
+    /// a spaceship and an oscillator, say, the whole board only comes back
+    /// in place, once the spaceship went around the universe. A shifted
+    /// repeat is recognised by the shape of the live cells within their
+    /// `live_bounding_box`, so not while a pattern is crossing an edge of
+    /// the universe. States are compared by hash, like in
+    /// `tick_until_repeat`.
+    pub fn classify(&mut self, max_generations: u32) -> String {
+        let shape_hash = |universe: &Universe| match universe.live_bounding_box() {
+            Some((row, col, height, width)) => universe.subgrid(row, col, height, width).state_hash(),
+            None => 0,
+        };
+
+        let mut states = HashMap::new();
+        let mut shapes = HashMap::new();
+        states.insert(self.state_hash(), self.generation);
+        shapes.insert(shape_hash(self), self.generation);
+        for _ in 0..max_generations {
+            self.tick();
+            if let Some(&generation) = states.get(&self.state_hash()) {
+                return match self.generation - generation {
+                    1 => "still-life".to_string(),
+                    period => format!("oscillator(period={})", period),
+                };
+            }
+            if let Some(&generation) = shapes.get(&shape_hash(self)) {
+                return format!("spaceship(period={})", self.generation - generation);
+            }
+            if states.len() < MAX_REPEAT_STATES {
+                states.insert(self.state_hash(), self.generation);
+                shapes.insert(shape_hash(self), self.generation);
+            }
+        }
+        "unknown/growing".to_string()
+    }
+
+    /// Tick at most `max_generations` times until two groups of connected
+    /// live cells meet, e.g. to time a collision between gliders, and
+    /// return the generation at which they did, or `None` if they did not.
+    /// The universe is left advanced, at that generation if any.
+    ///
+    /// Groups are taken to have met when a tick leaves fewer of them than
+    /// there were before it, which is a heuristic: a group that dies out
+    /// on its own, like a lone cell, counts as well, and a pattern made of
+    /// several groups, like a beehive next to a blinker, may count too.
+    pub fn first_interaction(&mut self, max_generations: u32) -> Option<u32> {
+        let mut groups = self.clusters().len();
+        for _ in 0..max_generations {
+            self.tick();
+            let count = self.clusters().len();
+            if count < groups {
+                return Some(self.generation);
+            }
+            groups = count;
+        }
+        None
+    }
+
+    /// Average population left after `generations` ticks on `trials` random
+    /// boards of the same dimensions, density and rule as this one, e.g. to
+    /// tell how much of a random field a rule typically leaves alive. The
+    /// boards are drawn from `seed`, so the same seed gives the same
+    /// estimate, and 0 trials give 0.
+    ///
+    /// This universe is not modified. The cost is that of ticking a copy
+    /// of it `trials * generations` times, which adds up quickly on large
+    /// boards.
+    pub fn estimate_steady_population(&self, trials: u32, generations: u32, seed: u64) -> f64 {
+        let size = (self.width * self.height) as f64;
+        let density = if size > 0.0 { self.population() as f64 / size } else { 0.0 };
+
+        let mut rng = utils::Rng::new(seed);
+        let mut total = 0.0;
+        for _ in 0..trials {
+            let mut universe = self.clone();
+            universe.recording = false;
+            universe.clear_all_cells();
+            universe.seed_dead_cells(density, Some(rng.next_u64()));
+            universe.tick_n(generations);
+            total += universe.population() as f64;
+        }
+        if trials == 0 { 0.0 } else { total / trials as f64 }
+    }
+
+    /// Advance the universe by `generations` ticks, and return how many
+    /// different states it went through, counting the current one, e.g. 1
+    /// for a still life, 2 for a blinker and many for a long-lived
+    /// methuselah before it settles.
+    ///
+    /// States are compared by `state_hash`, like in `tick_until_repeat`,
+    /// and at most `MAX_REPEAT_STATES` of them are remembered, which caps
+    /// the count.
+    pub fn distinct_states(&mut self, generations: u32) -> u32 {
+        let mut seen = HashSet::new();
+        seen.insert(self.state_hash());
+        for _ in 0..generations {
+            self.tick();
+            if seen.len() < MAX_REPEAT_STATES {
+                seen.insert(self.state_hash());
+            }
+        }
+        seen.len() as u32
+    }
+
+    /// Advance the universe by `generations` ticks, and return the slope of
+    /// the least squares line through the population at each generation,
+    /// the current one included, in cells per generation: clearly positive
+    /// for a board growing without bound, e.g. with a gun, close to 0 for
+    /// one that settled into still lifes and oscillators, and negative for
+    /// one dying out. Without any tick, this is 0.
+    pub fn growth_trend(&mut self, generations: u32) -> f64 {
+        let mut populations = Vec::with_capacity(generations as usize + 1);
+        populations.push(self.population() as f64);
+        for _ in 0..generations {
+            self.tick();
+            populations.push(self.population() as f64);
+        }
+
+        let count = populations.len() as f64;
+        let mean_x = (count - 1.0) / 2.0;
+        let mean_y = populations.iter().sum::<f64>() / count;
+        let (mut covariance, mut variance) = (0.0, 0.0);
+        for (x, y) in populations.iter().enumerate() {
+            let dx = x as f64 - mean_x;
+            covariance += dx * (y - mean_y);
+            variance += dx * dx;
+        }
+        if variance == 0.0 {
+            0.0
+        } else {
+            covariance / variance
+        }
+    }
+
+    /// Tick until the population reaches `threshold`, at most
+    /// `max_generations` times, returning the generation at which it first
+    /// did, or `None` if it did not within the limit. If the population is
+    /// already there, this returns the current generation without ticking.
+    ///
+    /// Since a board that stops changing, e.g. one that filled all the space
+    /// a growth rule lets it fill, can never get any further, this gives up
+    /// and returns `None` as soon as a tick leaves the board unchanged.
+    pub fn run_until_population(&mut self, threshold: u32, max_generations: u32) -> Option<u32> {
+        if self.population() >= threshold {
+            return Some(self.generation);
+        }
+
+        for _ in 0..max_generations {
+            self.tick();
+            if self.population() >= threshold {
+                return Some(self.generation);
+            }
+            if self.births + self.deaths == 0 {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// Coordinates of every live cell as a flat list of alternating row and
+    /// column values, `[row0, col0, row1, col1, ...]`, in row-major order.
+    ///
+    /// On sparse boards this is much less to copy from wasm memory than the
+    /// whole cell buffer; its length is given by `live_cells_flat_len`.
+    pub fn live_cells_flat(&self) -> Vec<u32> {
+        self.live_cells()
+            .into_iter()
+            .flat_map(|(row, col)| [row, col])
+            .collect()
+    }
+
+    /// The lengths of the runs of dead and live cells along `row`, from left
+    /// to right, alternating and starting with dead cells, so the first
+    /// length is 0 when the row starts with a live cell, e.g. for a renderer
+    /// to draw spans instead of single cells.
+    ///
+    /// Fails if the row is outside of the universe.
+    pub fn row_runs(&self, row: u32) -> Result<Vec<u32>, Error> {
+        if row >= self.height {
+            return Err(Error::OutOfBounds {
+                row,
+                column: 0,
+                width: self.width,
+                height: self.height,
+            });
+        }
+
+        let mut runs = vec![0];
+        let mut alive = false;
+        for col in 0..self.width {
+            if self.cells[self.get_index(row, col)] != alive {
+                alive = !alive;
+                runs.push(0);
+            }
+            *runs.last_mut().unwrap() += 1;
+        }
+        Ok(runs)
+    }
+
+    /// Number of values returned by `live_cells_flat`, i.e. twice the
+    /// population.
+    pub fn live_cells_flat_len(&self) -> usize {
+        2 * self.population() as usize
+    }
+
+    /// `preview_cell` as a list of 3 numbers, `[neighbors, alive, alive
+    /// next]`, with 1 for true and 0 for false, since tuples cannot be
+    /// returned to JavaScript.
+    pub fn preview_cell_flat(&self, row: u32, column: u32) -> Result<Vec<u8>, Error> {
+        let (neighbors, alive, next) = self.preview_cell(row, column)?;
+        Ok(vec![neighbors, alive as u8, next as u8])
+    }
+
+    /// Whether the live cells of `other` form the same pattern as those of
+    /// this universe, up to its position and any of the 8 rotations and
+    /// reflections of the square, which is when they are the same pattern
+    /// for a pattern database.
+    ///
+    /// Patterns are compared within their `live_bounding_box`, so one
+    /// crossing an edge of a universe is not matched with its unwrapped
+    /// copy.
+    pub fn is_equivalent(&self, other: &Universe) -> bool {
+        let as_i64 = |universe: &Universe| -> Vec<(i64, i64)> {
+            universe
+                .live_cells()
+                .into_iter()
+                .map(|(r, c)| (r as i64, c as i64))
+                .collect()
+        };
+        same_shape(&as_i64(self), &normalize_shape(as_i64(other)))
+    }
+
+    /// A representative of the pattern on the board that does not depend on
+    /// its position or orientation, e.g. to find duplicates in a collection
+    /// of patterns: its live cells, trimmed to their bounding box, in
+    /// whichever of the 8 rotations and reflections lists them first in
+    /// lexicographic order, as a universe just large enough for them, with
+    /// the same rule.
+    ///
+    /// Two patterns give the same canonical form exactly when they are
+    /// equivalent, see `is_equivalent`. An empty board gives an empty
+    /// universe.
+    pub fn canonical_form(&self) -> Universe {
+        let cells: Vec<(i64, i64)> = self
+            .live_cells()
+            .into_iter()
+            .map(|(r, c)| (r as i64, c as i64))
+            .collect();
+        let mut universe = Universe::from_shape(&canonical_shape(&cells));
+        universe.birth = self.birth;
+        universe.survival = self.survival;
+        universe.set_radius(self.radius);
+        universe
+    }
+
+    /// The `state_hash` of the `canonical_form` of the board, which is the
+    /// same for a pattern in any position, rotation or reflection, e.g. to
+    /// look up a pattern in a set of already known ones.
+    pub fn orientation_invariant_hash(&self) -> u64 {
+        self.canonical_form().state_hash()
+    }
+
+    /// Number of cells in the largest group of connected live cells, as
+    /// coloured by `cluster_colors`, or 0 on an empty board, e.g. to follow
+    /// how fragmented a field is over time.
+    pub fn largest_cluster_size(&self) -> u32 {
+        self.clusters().iter().map(|cluster| cluster.len() as u32).max().unwrap_or(0)
+    }
+
+    /// For every cell in row-major order, 0 if it is dead, and otherwise a
+    /// colour for the group of connected live cells it belongs to, as a
+    /// `0xRRGGBB` value, so that a renderer can paint each group in its own
+    /// colour.
+    ///
+    /// The colour is a hash of the position of the group's first cell in
+    /// row-major order, so the same board is always coloured the same way,
+    /// and neighbouring groups are unlikely to get similar colours. Two
+    /// groups may still get the same one, though rarely.
+    pub fn cluster_colors(&self) -> Vec<u32> {
+        let (height, width) = (self.height as i64, self.width as i64);
+        let mut colors = vec![0; (self.width * self.height) as usize];
+        for cluster in self.clusters() {
+            let indices: Vec<usize> = cluster
+                .iter()
+                .map(|&(r, c)| self.get_index(r.rem_euclid(height) as u32, c.rem_euclid(width) as u32))
+                .collect();
+            let first = *indices.iter().min().expect("clusters are not empty");
+
+            // The finalizer of MurmurHash3, which mixes every bit of the
+            // index into every bit of the colour.
+            let mut hash = first as u32;
+            hash ^= hash >> 16;
+            hash = hash.wrapping_mul(0x85eb_ca6b);
+            hash ^= hash >> 13;
+            hash = hash.wrapping_mul(0xc2b2_ae35);
+            hash ^= hash >> 16;
+            let color = (hash & 0x00ff_ffff).max(1);
+
+            for idx in indices {
+                colors[idx] = color;
+            }
+        }
+        colors
+    }
+
+    /// Magnitudes of the discrete Fourier transform of row `row`, taking
+    /// live cells as 1 and dead cells as 0: entry `k` measures how much the
+    /// row repeats `k` times across the width. Entry 0 is the number of live
+    /// cells, and a row striped every `p` cells peaks at multiples of
+    /// `width / p`.
+    ///
+    /// This is a naive transform, quadratic in the width, which is fine for
+    /// the width of a board. Fails if the row is outside of the universe.
+    pub fn row_spectrum(&self, row: u32) -> Result<Vec<f64>, Error> {
+        self.checked_index(row, 0)?;
+
+        let width = self.width as usize;
+        let cells: Vec<usize> = (0..width)
+            .filter(|&col| self.cells[self.get_index(row, col as u32)])
+            .collect();
+        Ok((0..width)
+            .map(|k| {
+                let (mut re, mut im) = (0.0, 0.0);
+                for &n in cells.iter() {
+                    let angle = -2.0 * std::f64::consts::PI * ((k * n) % width) as f64 / width as f64;
+                    re += angle.cos();
+                    im += angle.sin();
+                }
+                (re * re + im * im).sqrt()
+            })
+            .collect())
+    }
+
+    /// Number of cells whose state differs between this universe and
+    /// `target`, which must have the same dimensions.
+    pub fn hamming_distance(&self, target: &Universe) -> Result<u32, Error> {
+        if self.width != target.width || self.height != target.height {
+            return Err(Error::DimensionMismatch {
+                width: self.width,
+                height: self.height,
+                other_width: target.width,
+                other_height: target.height,
+            });
+        }
+
+        Ok(self
+            .logical_words()
+            .zip(target.logical_words())
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum())
+    }
+
+    /// Return a copy of this universe with every cell inverted, live cells
+    /// becoming dead and dead cells becoming alive, leaving this universe
+    /// unchanged.
+    pub fn complement(&self) -> Universe {
+        let mut universe = self.clone();
+        universe.cells.toggle_range(..(self.width * self.height) as usize);
+        universe.edited = true;
+        universe
+    }
+
+    /// Whether ticking the `complement` of the board gives the complement of
+    /// ticking the board, without ticking either.
+    ///
+    /// This holds for every board under a self-complementary rule such as
+    /// Day & Night (B3678/S34678), but with Conway's rule it is almost never
+    /// true: the complement of a sparse board is a crowded one, in which
+    /// nearly every cell dies of overpopulation.
+    pub fn is_complement_stable(&self) -> bool {
+        let mut next = self.next_generation().cells;
+        next.toggle_range(..(self.width * self.height) as usize);
+        self.complement().next_generation().cells == next
+    }
+
+    pub fn render(&self) -> String {
+        self.to_string()
+    }
+
+    /// Render the board with one emoji per cell and a newline after each
+    /// row, for posting small patterns in chat apps, where emoji are shown
+    /// at the same width whatever the font.
+    ///
+    /// The emoji can be changed with `set_emoji`, and default to ⬛ for live
+    /// cells and ⬜ for dead ones.
+    pub fn render_emoji(&self) -> String {
+        let mut out = String::new();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let cell = self.cells[self.get_index(row, col)];
+                out.push_str(if cell { &self.emoji_alive } else { &self.emoji_dead });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Set the symbols `render_emoji` uses for live and dead cells.
+    pub fn set_emoji(&mut self, alive: &str, dead: &str) {
+        self.emoji_alive = alive.to_string();
+        self.emoji_dead = dead.to_string();
+    }
+}
+
+impl Universe {
+    // Build a universe with the default rule around already computed cells.
+    // Every constructor goes through this, so that new fields only need to be
+    // initialised here.
+    fn from_bitset(width: u32, height: u32, cells: FixedBitSet) -> Universe {
+        Universe {
+            width,
+            height,
+            cells,
+            birth: CONWAY_BIRTH,
+            survival: CONWAY_SURVIVAL,
+            radius: 1,
+            rule_region: None,
+            frozen: FixedBitSet::new(),
+            ant: None,
+            boundary: Boundary::Toroidal,
+            escaped: 0,
+            generation: 0,
+            peak_population: 0,
+            peak_generation: 0,
+            history: VecDeque::new(),
+            history_limit: 0,
+            births: 0,
+            deaths: 0,
+            stable_for: 0,
+            edited: false,
+            log_interval: 0,
+            steps_per_call: 1,
+            injection_ticks: 0,
+            undo: VecDeque::new(),
+            undo_limit: 0,
+            recording: false,
+            script: Vec::new(),
+            emoji_alive: DEFAULT_EMOJI_ALIVE.to_string(),
+            emoji_dead: DEFAULT_EMOJI_DEAD.to_string(),
+            row_offsets: neighbor_offsets(height, 1),
+            col_offsets: neighbor_offsets(width, 1),
+            neighbor_counts: None,
+        }
+    }
+
+    /// Build a universe from the state of each cell in row-major order, as
+    /// returned by `to_vec_bool`.
+    ///
+    /// Panics if `cells` does not hold exactly `width * height` values.
+    pub fn from_vec_bool(width: u32, height: u32, cells: &[bool]) -> Universe {
+        let size = (width * height) as usize;
+        assert_eq!(cells.len(), size, "expected {}x{} cells", width, height);
+
+        let mut bits = FixedBitSet::with_capacity(size);
+        for (i, cell) in cells.iter().cloned().enumerate() {
+            bits.set(i, cell);
+        }
+
+        Universe::from_bitset(width, height, bits)
+    }
+
+    /// Build a `width` by `height` universe whose top row is `seed_row` and
+    /// whose other cells are dead, e.g. as a reproducible start for
+    /// `step_elementary` or `tick`.
+    ///
+    /// A seed row shorter than the universe is repeated along the top row,
+    /// and one longer than it is truncated. An empty seed row leaves the
+    /// board empty. Fails if the universe would have no cells.
+    pub fn new_from_seed_row(width: u32, height: u32, seed_row: &[bool]) -> Result<Universe, Error> {
+        if width == 0 || height == 0 {
+            return Err(Error::TooSmall {
+                width,
+                height,
+                min_width: 1,
+                min_height: 1,
+            });
+        }
+
+        let mut cells = FixedBitSet::with_capacity((width * height) as usize);
+        for (col, &alive) in seed_row.iter().cycle().take(width as usize).enumerate() {
+            cells.set(col, alive);
+        }
+        Ok(Universe::from_bitset(width, height, cells))
+    }
+
+    /// Build an otherwise empty universe in which the cells at the given
+    /// `(row, column)` coordinates are alive.
+    ///
+    /// Panics if any coordinate is outside of the universe.
+    pub fn from_cells(width: u32, height: u32, cells: &[(u32, u32)]) -> Universe {
+        let size = (width * height) as usize;
+        let mut universe = Universe::from_bitset(width, height, FixedBitSet::with_capacity(size));
+        for (row, col) in cells.iter().cloned() {
+            universe.set_cell(row, col, true).unwrap_or_else(|error| panic!("{}", error));
+        }
+        universe
+    }
+
+    /// Build a universe with one row per string, in which every `alive`
+    /// character is a live cell and any other is dead, e.g. for tests:
+    /// `Universe::from_rows(&[".#.", "..#", "###"], '#')` is a glider.
+    ///
+    /// The universe is as wide as the longest row, and shorter rows are
+    /// padded with dead cells.
+    pub fn from_rows(rows: &[&str], alive: char) -> Universe {
+        let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0) as u32;
+        let height = rows.len() as u32;
+
+        let mut cells = FixedBitSet::with_capacity((width * height) as usize);
+        for (r, row) in rows.iter().enumerate() {
+            for (c, symbol) in row.chars().enumerate() {
+                if symbol == alive {
+                    cells.insert(r * width as usize + c);
+                }
+            }
+        }
+        Universe::from_bitset(width, height, cells)
+    }
+
+    /// The `(row, column)` coordinates of every live cell, in row-major
+    /// order.
+    pub fn live_cells(&self) -> Vec<(u32, u32)> {
+        self.cells
+            .ones()
+            .take_while(|&i| i < (self.width * self.height) as usize)
+            .map(|i| (i as u32 / self.width, i as u32 % self.width))
+            .collect()
+    }
+
+    /// The state of each cell in row-major order, `true` meaning alive.
+    pub fn to_vec_bool(&self) -> Vec<bool> {
+        (0 .. (self.width * self.height) as usize)
+            .map(|i| self.cells[i])
+            .collect()
+    }
+
+    /// The highest population seen so far and the generation at which it was
+    /// first seen, as `(population, generation)`.
+    ///
+    /// This covers the current board and every board produced by `tick`
+    /// since the universe was created or last cleared with
+    /// `clear_all_cells`.
+    pub fn peak_population(&self) -> (u32, u32) {
+        let population = self.population();
+        if population > self.peak_population {
+            (population, self.generation)
+        } else {
+            (self.peak_population, self.peak_generation)
+        }
+    }
+
+    // The words of the cell buffer that hold the `width * height` cells of
+    // the universe, with any bits past the last cell cleared, so that stale
+    // bits left over from a resize are never taken into account.
+    fn logical_words(&self) -> impl Iterator<Item = u32> + '_ {
+        let size = (self.width * self.height) as usize;
+        let words = size.div_ceil(32);
+        let rest = size % 32;
+
+        self.cells.as_slice()[..words].iter().enumerate().map(move |(i, &word)| {
+            if i + 1 == words && rest != 0 {
+                word & ((1 << rest) - 1)
+            } else {
+                word
+            }
+        })
+    }
+
+    // A universe just large enough for a normalized shape, see
+    // `normalize_shape`, with its cells alive.
+    fn from_shape(shape: &[(i64, i64)]) -> Universe {
+        let height = shape.iter().map(|&(r, _)| r as u32 + 1).max().unwrap_or(0);
+        let width = shape.iter().map(|&(_, c)| c as u32 + 1).max().unwrap_or(0);
+        let mut bits = FixedBitSet::with_capacity((width * height) as usize);
+        for &(r, c) in shape {
+            bits.insert((r as u32 * width + c as u32) as usize);
+        }
+        Universe::from_bitset(width, height, bits)
+    }
+
+    /// Let the board settle, then count its "ash", the common small objects
+    /// left over once a random field has burnt out, as `(name, count)`
+    /// pairs.
+    ///
+    /// The universe first ticks until it is back to a state it was already
+    /// in, like `tick_until_repeat`, at most `max_generations` times; the
+    /// census is taken even if it has not settled by then. The objects
+    /// recognised are the block, blinker, beehive, loaf and boat, in that
+    /// order, with the names of `Pattern::name`, in any orientation and in
+    /// either phase of the blinker. Each group of connected live cells counts
+    /// as one object, so two objects touching each other, like anything
+    /// else, are not counted.
+    pub fn ash_census(&mut self, max_generations: u32) -> Vec<(String, u32)> {
+        const ASH: [Pattern; 5] = [Pattern::Block, Pattern::Blinker, Pattern::Beehive, Pattern::Loaf, Pattern::Boat];
+
+        self.tick_until_repeat(max_generations);
+
+        let clusters = self.clusters();
+        ASH.iter()
+            .map(|&pattern| {
+                let target: Vec<(i64, i64)> =
+                    pattern.cells().iter().map(|&(r, c)| (r as i64, c as i64)).collect();
+                let target = normalize_shape(target);
+                let count = clusters.iter().filter(|cluster| same_shape(cluster, &target)).count();
+                (pattern.name().to_string(), count as u32)
+            })
+            .collect()
+    }
+
+    /// The smallest rectangle holding every live cell, as
+    /// `(row, column, height, width)` with `(row, column)` its top-left
+    /// cell, or `None` if there are no live cells.
+    ///
+    /// The rectangle does not wrap around the edges, so a pattern crossing
+    /// one is covered by a rectangle spanning the whole universe.
+    pub fn live_bounding_box(&self) -> Option<(u32, u32, u32, u32)> {
+        let cells = self.live_cells();
+        let min_row = cells.first()?.0;
+        let max_row = cells.last()?.0;
+        let min_col = cells.iter().map(|&(_, col)| col).min()?;
+        let max_col = cells.iter().map(|&(_, col)| col).max()?;
+        Some((min_row, min_col, max_row - min_row + 1, max_col - min_col + 1))
+    }
+
+    /// The rectangle of cells that the live cells can affect within
+    /// `generations` ticks, since a cell only affects its neighbours at
+    /// each tick, as `(row, column, height, width)` with `(row, column)` its
+    /// top-left cell, e.g. to tell how much room a pattern needs. This is
+    /// the `live_bounding_box` grown by `generations` times the radius of
+    /// the neighbourhood on each side, or `(0, 0, 0, 0)` without live
+    /// cells.
+    ///
+    /// Since the universe wraps around, the rectangle may go past the
+    /// bottom or right edge, carrying on from the top or left, and it
+    /// spans the whole height or width of the board once it would overlap
+    /// itself. Under a dead boundary it is clipped to the board instead.
+    pub fn influence_region(&self, generations: u32) -> (u32, u32, u32, u32) {
+        let (row, col, height, width) = match self.live_bounding_box() {
+            Some(bounding_box) => bounding_box,
+            None => return (0, 0, 0, 0),
+        };
+        let reach = generations as u64 * self.radius as u64;
+
+        let grow = |start: u32, length: u32, size: u32| -> (u32, u32) {
+            let (start, length, size) = (start as u64, length as u64, size as u64);
+            if self.boundary == Boundary::Dead {
+                let end = (start + length + reach).min(size);
+                let start = start.saturating_sub(reach);
+                (start as u32, (end - start) as u32)
+            } else if length + 2 * reach >= size {
+                (0, size as u32)
+            } else {
+                let start = (start + size - reach) % size;
+                (start as u32, (length + 2 * reach) as u32)
+            }
+        };
+        let (row, height) = grow(row, height, self.height);
+        let (col, width) = grow(col, width, self.width);
+        (row, col, height, width)
+    }
+
+    /// Whether each row, from top to bottom, has an odd number of live
+    /// cells. Transformations of the board move these around predictably,
+    /// e.g. `transpose` swaps them with the `column_parities`, which makes
+    /// them a cheap check of such transformations.
+    pub fn row_parities(&self) -> Vec<bool> {
+        let mut parities = vec![false; self.height as usize];
+        for (row, _) in self.live_cells() {
+            parities[row as usize] ^= true;
+        }
+        parities
+    }
+
+    /// Whether each column, from left to right, has an odd number of live
+    /// cells, like `row_parities`.
+    pub fn column_parities(&self) -> Vec<bool> {
+        let mut parities = vec![false; self.width as usize];
+        for (_, col) in self.live_cells() {
+            parities[col as usize] ^= true;
+        }
+        parities
+    }
+
+    /// Advance the universe by `generations` ticks, and return the
+    /// `live_bounding_box` after each of them, or `(0, 0, 0, 0)` after
+    /// those that leave no live cells, e.g. to plot its area over time,
+    /// which stays bounded for oscillators and keeps growing for patterns
+    /// that grow.
+    pub fn bounding_box_history(&mut self, generations: u32) -> Vec<(u32, u32, u32, u32)> {
+        (0..generations)
+            .map(|_| {
+                self.tick();
+                self.live_bounding_box().unwrap_or((0, 0, 0, 0))
+            })
+            .collect()
+    }
+
+    /// The number of live cells on the top, bottom, left and right edges of
+    /// the universe, in that order, which is where a pattern starts to
+    /// interact with itself across the edges; see also `touches_edge`.
+    ///
+    /// Corner cells count towards both of their edges, and in a universe a
+    /// single row high, or a single column wide, opposite edges are the same.
+    pub fn edge_populations(&self) -> (u32, u32, u32, u32) {
+        if self.width == 0 || self.height == 0 {
+            return (0, 0, 0, 0);
+        }
+
+        let row_population = |row| (0..self.width).filter(|&col| self.cells[self.get_index(row, col)]).count();
+        let col_population = |col| (0..self.height).filter(|&row| self.cells[self.get_index(row, col)]).count();
+        (
+            row_population(0) as u32,
+            row_population(self.height - 1) as u32,
+            col_population(0) as u32,
+            col_population(self.width - 1) as u32,
+        )
+    }
+
+    // Whether both universes have the same dimensions and cells, regardless
+    // of anything else such as their rule or generation.
+    fn same_cells(&self, other: &Universe) -> bool {
+        self.width == other.width
+            && self.height == other.height
+            && self.logical_words().eq(other.logical_words())
+    }
+
+    /// The number of live neighbours of the cell at `(row, column)`,
+    /// whether it is alive, and whether it will be alive after the next
+    /// tick under the current rule, without ticking.
+    ///
+    /// Meant for explaining why a cell lives or dies. Fails if the cell is
+    /// outside of the universe.
+    pub fn preview_cell(&self, row: u32, column: u32) -> Result<(u8, bool, bool), Error> {
+        let alive = self.get_cell(row, column)?;
+        let neighbors = self.live_neighbor_count(row, column);
+        let idx = self.get_index(row, column);
+        Ok((neighbors, alive, self.next_cell_state_at(idx, alive, neighbors)))
+    }
+
+    /// For each number of live neighbours from 0 to 8, how many live cells
+    /// currently have that many.
+    ///
+    /// Lots of cells below 2 or above 3 mean the board is about to thin out
+    /// under Conway's rule. Under `Life24`, where a cell has up to 24
+    /// neighbours, cells with more than 8 are counted in the last entry.
+    pub fn neighbor_histogram(&self) -> [u32; 9] {
+        let mut histogram = [0; 9];
+        for (row, col) in self.live_cells() {
+            histogram[self.live_neighbor_count(row, col).min(8) as usize] += 1;
+        }
+        histogram
+    }
+
+    // The connected groups of live cells, touching each other horizontally,
+    // vertically or diagonally, with the coordinates of their cells as
+    // `(row, column)`. Since the universe wraps around, the coordinates are
+    // not wrapped once a group crosses an edge, but go on past it,
+    // possibly becoming negative, so that every group keeps its shape.
+    fn clusters(&self) -> Vec<Vec<(i64, i64)>> {
+        let (height, width) = (self.height as i64, self.width as i64);
+        let mut visited = FixedBitSet::with_capacity((self.width * self.height) as usize);
+        let mut clusters = Vec::new();
+
+        for (row, col) in self.live_cells() {
+            let idx = self.get_index(row, col);
+            if visited[idx] {
+                continue;
+            }
+            visited.insert(idx);
+
+            let mut cluster = Vec::new();
+            let mut stack = vec![(row as i64, col as i64)];
+            while let Some((r, c)) = stack.pop() {
+                cluster.push((r, c));
+                for dr in -1..=1 {
+                    for dc in -1..=1 {
+                        let (nr, nc) = (r + dr, c + dc);
+                        let idx = self.get_index(nr.rem_euclid(height) as u32, nc.rem_euclid(width) as u32);
+                        if self.cells[idx] && !visited[idx] {
+                            visited.insert(idx);
+                            stack.push((nr, nc));
+                        }
+                    }
+                }
+            }
+            clusters.push(cluster);
+        }
+        clusters
+    }
+
+    /// Follow the group of connected live cells containing `(row, col)`
+    /// through `generations` ticks, returning the `(row, column)` position
+    /// of its centroid before the first tick and after each of them.
+    ///
+    /// After each tick the group is taken to be the one whose centroid is
+    /// nearest to its previous centroid. Positions are not wrapped around
+    /// the edges, so a spaceship's path stays a straight line across them.
+    /// The trajectory stops early if the board becomes empty, and is empty
+    /// if the cell at `(row, col)` is dead or outside of the universe.
+    ///
+    /// This is only a heuristic: it loses track of the right group when
+    /// another one comes closer than the distance the group moves in a tick,
+    /// when the group splits, merges with another or is made of parts that
+    /// do not touch in some phases, and when it is too large for its
+    /// centroid to be well defined on a board that wraps around.
+    pub fn track_cluster(&mut self, row: u32, col: u32, generations: u32) -> Vec<(f64, f64)> {
+        if !self.try_get_index(row, col).is_some_and(|idx| self.cells[idx]) {
+            return Vec::new();
+        }
+
+        let start = self
+            .clusters()
+            .into_iter()
+            .find(|cluster| cluster.contains(&(row as i64, col as i64)))
+            .expect("a live cell belongs to a cluster");
+        let mut trajectory = vec![centroid(&start)];
+
+        for _ in 0..generations {
+            self.tick();
+
+            // Shift each centroid by whole turns around the universe to its
+            // position closest to the previous one.
+            let (prev_row, prev_col) = trajectory[trajectory.len() - 1];
+            let closest = |value: f64, previous: f64, size: u32| {
+                let size = size as f64;
+                value + ((previous - value) / size).round() * size
+            };
+            let next = self
+                .clusters()
+                .iter()
+                .map(|cluster| {
+                    let (r, c) = centroid(cluster);
+                    (closest(r, prev_row, self.height), closest(c, prev_col, self.width))
+                })
+                .min_by(|a, b| {
+                    let distance = |(r, c): (f64, f64)| (r - prev_row).powi(2) + (c - prev_col).powi(2);
+                    distance(*a).total_cmp(&distance(*b))
+                });
+
+            match next {
+                Some(position) => trajectory.push(position),
+                None => break,
+            }
+        }
+        trajectory
+    }
+
+    /// Distance between the centroids of every two groups of connected live
+    /// cells, going across the edges when that is shorter as in
+    /// `toroidal_distance`, e.g. to see which groups may meet first. Groups
+    /// are numbered in the row-major order of their first cell, and the
+    /// distances come for the pairs (0, 1), (0, 2), ..., (1, 2), and so on.
+    ///
+    /// There are `k * (k - 1) / 2` distances for `k` groups, so this gets
+    /// slow on boards holding many of them.
+    pub fn cluster_distances(&self) -> Vec<f64> {
+        let wrapped = |a: f64, b: f64, size: u32| {
+            let direct = (a - b).rem_euclid(size as f64);
+            direct.min(size as f64 - direct)
+        };
+
+        let centroids: Vec<(f64, f64)> = self.clusters().iter().map(|cluster| centroid(cluster)).collect();
+        let mut distances = Vec::new();
+        for (i, &(r1, c1)) in centroids.iter().enumerate() {
+            for &(r2, c2) in &centroids[i + 1..] {
+                let rows = wrapped(r1, r2, self.height);
+                let cols = wrapped(c1, c2, self.width);
+                distances.push((rows * rows + cols * cols).sqrt());
+            }
+        }
+        distances
+    }
+
+    // Whether the `height` by `width` window whose top-left cell is
+    // `(row, column)` holds exactly the live cells at the given offsets
+    // within it and no others. The window wraps around the edges.
+    fn matches_template(&self, row: u32, column: u32, height: u32, width: u32, live: &[(u32, u32)]) -> bool {
+        (0..height).all(|r| {
+            (0..width).all(|c| {
+                let idx = self.get_index((row + r) % self.height, (column + c) % self.width);
+                self.cells[idx] == live.contains(&(r, c))
+            })
+        })
+    }
+
+    /// The `(row, column)` coordinates of the center of every blinker, the
+    /// period 2 oscillator made of three cells in a row, either horizontal
+    /// or vertical, sorted in row-major order.
+    ///
+    /// A blinker is only recognised when no other live cell touches it, so
+    /// that it is guaranteed to keep oscillating on its own.
+    pub fn blinker_sites(&self) -> Vec<(u32, u32)> {
+        const HORIZONTAL: [(u32, u32); 3] = [(1, 1), (1, 2), (1, 3)];
+        const VERTICAL: [(u32, u32); 3] = [(1, 1), (2, 1), (3, 1)];
+
+        let mut sites = Vec::new();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                if self.matches_template(row, col, 3, 5, &HORIZONTAL) {
+                    sites.push(((row + 1) % self.height, (col + 2) % self.width));
+                }
+                if self.matches_template(row, col, 5, 3, &VERTICAL) {
+                    sites.push(((row + 2) % self.height, (col + 1) % self.width));
+                }
+            }
+        }
+        sites.sort_unstable();
+        sites
+    }
+
+    /// Get the dead and alive values of the entire universe.
+    pub fn get_cells(&self) -> &FixedBitSet {
+        &self.cells
+    }
+
+    /// Set cells to be alive in a universe by passing the row and column
+    /// of each cell as an array. Cells outside of the universe are skipped.
+    ///
+    /// Like the other editing methods below, this returns the universe so
+    /// that edits can be chained:
+    /// `universe.clear().fill_rect(0, 0, 2, 2, true).insert_glider(5, 5)`.
+    pub fn set_cells(&mut self, cells: &[(u32, u32)]) -> &mut Self {
+        for (row, col) in cells.iter().cloned() {
+            if let Some(idx) = self.try_get_index(row, col) {
+                self.cells.insert(idx);
+                self.edited = true;
+                self.record(|| format!("set_cell {} {} 1", row, col));
+            }
+        }
+        self
+    }
+
+    /// Make each cell of a list of `(row, column, alive)` edits alive or
+    /// dead, in order, e.g. to paste a selection in one call rather than
+    /// one `set_cell` per cell.
+    ///
+    /// The edits are all applied or none is: if any coordinate is outside
+    /// of the universe, this fails with the first of them and leaves the
+    /// universe unchanged.
+    pub fn apply_edits(&mut self, edits: &[(u32, u32, bool)]) -> Result<(), Error> {
+        let indices = edits
+            .iter()
+            .map(|&(row, col, _)| self.checked_index(row, col))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (idx, &(row, col, alive)) in indices.into_iter().zip(edits) {
+            self.cells.set(idx, alive);
+            self.edited = true;
+            self.record(|| format!("set_cell {} {} {}", row, col, alive as u8));
+        }
+        Ok(())
+    }
+
+    /// Kill every cell, like `clear_all_cells`.
+    pub fn clear(&mut self) -> &mut Self {
+        self.clear_all_cells();
+        self
+    }
+
+    /// Make every cell of the `height` by `width` rectangle whose top-left
+    /// cell is `(row, col)` alive or dead. The rectangle is clipped to the
+    /// bounds of the universe.
+    pub fn fill_rect(&mut self, row: u32, col: u32, height: u32, width: u32, alive: bool) -> &mut Self {
+        let rows = row.min(self.height)..row.saturating_add(height).min(self.height);
+        for r in rows {
+            let start = self.get_index(r, col.min(self.width));
+            let end = self.get_index(r, col.saturating_add(width).min(self.width));
+            self.cells.set_range(start..end, alive);
+        }
+        self.edited = true;
+        self.record(|| format!("fill_rect {} {} {} {} {}", row, col, height, width, alive as u8));
+        self
+    }
+
+    /// Flip every cell of the `height` by `width` rectangle whose top-left
+    /// cell is `(row, col)` between alive and dead, e.g. to invert a
+    /// selection. The rectangle is clipped to the bounds of the universe.
+    pub fn toggle_rect(&mut self, row: u32, col: u32, height: u32, width: u32) -> &mut Self {
+        let rows = row.min(self.height)..row.saturating_add(height).min(self.height);
+        let start_col = col.min(self.width);
+        let end_col = col.saturating_add(width).min(self.width);
+        if start_col == 0 && end_col == self.width {
+            // Whole rows are contiguous, so they can be flipped at once.
+            let start = self.get_index(rows.start, 0);
+            let end = self.get_index(rows.end, 0);
+            self.cells.toggle_range(start..end);
+        } else {
+            for r in rows {
+                let start = self.get_index(r, start_col);
+                let end = self.get_index(r, end_col);
+                self.cells.toggle_range(start..end);
+            }
+        }
+        self.edited = true;
+        self.record(|| format!("toggle_rect {} {} {} {}", row, col, height, width));
+        self
+    }
+
+    /// Bring to life the cells on the straight line from `(r0, c0)` to
+    /// `(r1, c1)`, both included, dropping those outside of the universe.
+    pub fn draw_line(&mut self, r0: u32, c0: u32, r1: u32, c1: u32) -> &mut Self {
+        self.record(|| format!("draw_line {} {} {} {}", r0, c0, r1, c1));
+
+        // Bresenham's algorithm, which steps one cell at a time along the
+        // longer axis and keeps the error to the ideal line below half a
+        // cell along the other.
+        let (mut r, mut c) = (r0 as i64, c0 as i64);
+        let (r1, c1) = (r1 as i64, c1 as i64);
+        let (dr, dc) = ((r1 - r).abs(), -(c1 - c).abs());
+        let (step_r, step_c) = ((r1 - r).signum(), (c1 - c).signum());
+        let mut error = dr + dc;
+        loop {
+            if let Some(idx) = self.try_get_index(r as u32, c as u32) {
+                self.cells.insert(idx);
+                self.edited = true;
+            }
+            if (r, c) == (r1, c1) {
+                break;
+            }
+            let double = 2 * error;
+            if double >= dc {
+                error += dc;
+                r += step_r;
+            }
+            if double <= dr {
+                error += dr;
+                c += step_c;
+            }
+        }
+        self
+    }
+
+    /// Add a glider with its top-left corner at `(row, column)`, like
+    /// `insert_pattern` with `Pattern::Glider`.
+    pub fn insert_glider(&mut self, row: u32, column: u32) -> &mut Self {
+        self.insert_pattern(Pattern::Glider, row, column);
+        self
+    }
+
+    /// Add a puffer train with its top-left corner at `(row, column)`, like
+    /// `insert_pattern` with `Pattern::PufferTrain`: it moves right by one
+    /// cell every two generations, spreading a trail of debris behind it,
+    /// which needs a board of a hundred cells or so on each side.
+    pub fn insert_puffer(&mut self, row: u32, column: u32) -> &mut Self {
+        self.insert_pattern(Pattern::PufferTrain, row, column);
+        self
+    }
+
+    /// Add a spacerake with its top-left corner at `(row, column)`, like
+    /// `insert_pattern` with `Pattern::Spacerake`: it moves right by one cell
+    /// every two generations, leaving behind a glider every 20 generations,
+    /// which flies up and right until it wraps around the universe.
+    pub fn insert_rake(&mut self, row: u32, column: u32) -> &mut Self {
+        self.insert_pattern(Pattern::Spacerake, row, column);
+        self
+    }
+
+}
+
+#[test]
+fn test_cells_word_len() {
+    // 64 * 64 cells take 128 words of 32 bits.
+    let universe = Universe::hardcoded_64_by_64();
+    assert_eq!(universe.cells_word_len(), 128);
+}
+
+#[test]
+fn test_display() {
+    let mut universe = Universe::new_hardcoded(16, 16);
+    universe.clear_all_cells();
+    print!("{}", universe);
+}
+
+#[test]
+fn test_add_glider_at_keeps_existing_cells() {
+    // (2, 2) is dead in the glider's neighbourhood around (3, 3).
+    let mut universe = Universe::from_cells(8, 8, &[(2, 2)]);
+
+    universe.add_glider_at(3, 3);
+    assert!(universe.get_cells()[universe.get_index(2, 2)]);
+
+    universe.new_glider_at(3, 3);
+    assert!(!universe.get_cells()[universe.get_index(2, 2)]);
+}
+
+#[test]
+fn test_new_glider_oriented_headings() {
+    // A glider moves one cell diagonally every four generations, here from
+    // around (6, 6) to around (5, 7), (7, 7), (7, 5) and (5, 5).
+    let moved: [&[(u32, u32)]; 4] = [
+        &[(4, 7), (4, 8), (5, 6), (5, 8), (6, 8)],
+        &[(6, 7), (7, 8), (8, 6), (8, 7), (8, 8)],
+        &[(6, 4), (7, 4), (7, 6), (8, 4), (8, 5)],
+        &[(4, 4), (4, 5), (4, 6), (5, 4), (6, 5)],
+    ];
+
+    for (orientation, expected) in moved.iter().enumerate() {
+        let mut universe = Universe::from_cells(12, 12, &[]);
+        universe.new_glider_oriented(6, 6, orientation as u8);
+        universe.tick_n(4);
+        assert_eq!(universe.live_cells(), expected.to_vec(), "orientation {}", orientation);
+    }
+}
+
+#[test]
+fn test_peek_next_matches_tick() {
+    let mut universe = Universe::new_seeded(16, 16, 3);
+    let before = universe.get_cells().clone();
+
+    let peeked = universe.peek_next();
+    assert_eq!(universe.get_cells(), &before);
+
+    universe.tick();
+    assert_eq!(universe.get_cells(), peeked.get_cells());
+}
+
+#[test]
+fn test_vec_bool_round_trip() {
+    let cells = vec![
+        false, false, false, false, false,
+        false, false, true,  false, false,
+        false, false, true,  false, false,
+        false, false, true,  false, false,
+        false, false, false, false, false,
+    ];
+    let mut universe = Universe::from_vec_bool(5, 5, &cells);
+    assert_eq!(universe.to_vec_bool(), cells);
+
+    universe.tick();
+    assert_eq!(universe.to_vec_bool(), vec![
+        false, false, false, false, false,
+        false, false, false, false, false,
+        false, true,  true,  true,  false,
+        false, false, false, false, false,
+        false, false, false, false, false,
+    ]);
+}
+
+#[test]
+fn test_from_cells_blinker() {
+    let mut universe = Universe::from_cells(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(universe.live_cells(), vec![(2, 1), (2, 2), (2, 3)]);
+
+    universe.tick();
+    assert_eq!(universe.live_cells(), vec![(1, 2), (2, 2), (3, 2)]);
+}
+
+#[test]
+fn test_peak_population() {
+    // An R-pentomino grows well beyond its initial population.
+    let mut universe = Universe::from_cells(
+        32,
+        32,
+        &[(15, 16), (15, 17), (16, 15), (16, 16), (17, 16)],
+    );
+    assert_eq!(universe.peak_population(), (5, 0));
+
+    for _ in 0..10 {
+        universe.tick();
+    }
+    let (peak, generation) = universe.peak_population();
+    assert!(peak > 5);
+    assert!(generation > 0 && generation <= 10);
+
+    universe.clear_all_cells();
+    assert_eq!(universe.peak_population(), (0, 10));
+}
+
+#[test]
+fn test_population_history_limit() {
+    let mut universe = Universe::from_cells(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    universe.set_history_limit(2);
+    for _ in 0..4 {
+        universe.tick();
+    }
+    assert_eq!(universe.population_history(), vec![3, 3]);
+
+    universe.clear_history();
+    assert!(universe.population_history().is_empty());
+}
+
+#[test]
+fn test_toroidal_distance() {
+    let universe = Universe::from_cells(10, 8, &[]);
+
+    assert_eq!(universe.toroidal_distance(2, 3, 2, 3), 0.0);
+    assert_eq!(universe.toroidal_distance(1, 1, 4, 5), 5.0);
+    // Across the left and right edges.
+    assert_eq!(universe.toroidal_distance(0, 0, 0, 9), 1.0);
+    // Across the top and bottom edges.
+    assert_eq!(universe.toroidal_distance(7, 4, 0, 4), 1.0);
+    // Across both seams at once.
+    assert_eq!(universe.toroidal_distance(0, 0, 7, 9), 2f64.sqrt());
+    // Halfway around is as far as it gets.
+    assert_eq!(universe.toroidal_distance(0, 0, 4, 5), (16.0f64 + 25.0).sqrt());
+
+    let mut flat = Universe::from_cells(10, 8, &[]);
+    flat.set_height(0);
+    assert_eq!(flat.toroidal_distance(3, 1, 5, 4), 3.0);
+}
+
+#[test]
+fn test_subgrid_is_clipped() {
+    let universe = Universe::from_cells(6, 6, &[(1, 1), (4, 4), (5, 5)]);
+
+    let region = universe.subgrid(1, 1, 2, 3);
+    assert_eq!((region.width(), region.height()), (3, 2));
+    assert_eq!(region.live_cells(), vec![(0, 0)]);
+
+    let corner = universe.subgrid(4, 4, 10, 10);
+    assert_eq!((corner.width(), corner.height()), (2, 2));
+    assert_eq!(corner.live_cells(), vec![(0, 0), (1, 1)]);
+}
+
+#[test]
+fn test_tick_on_empty_board() {
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    universe.tick();
+    assert_eq!(universe.population(), 0);
+    assert_eq!(universe.generation(), 1);
+    assert_eq!(universe.population_history(), vec![0]);
+
+    // A B0 region fills up even when the rest of the board can't.
+    universe.set_rule_region("B0/S", 2, 2, 3, 3).unwrap();
+    universe.tick();
+    assert_eq!(universe.population(), 9);
+}
+
+#[test]
+fn test_pattern_fits() {
+    let universe = Universe::from_cells(10, 8, &[]);
+    assert!(universe.pattern_fits(Pattern::Glider, 5, 7));
+    assert!(!universe.pattern_fits(Pattern::Glider, 5, 8));
+    assert!(!universe.pattern_fits(Pattern::GosperGliderGun, 0, 0));
+}
+
+#[test]
+fn test_insert_pattern_clips() {
+    let mut universe = Universe::from_cells(4, 4, &[]);
+    universe.insert_pattern(Pattern::Block, 3, 3);
+    assert_eq!(universe.live_cells(), vec![(3, 3)]);
+
+    // Offsets that overflow are outside of the universe too.
+    universe.insert_pattern(Pattern::Glider, u32::MAX - 1, 0);
+    let diagonal = Universe::from_cells(2, 2, &[(0, 0), (1, 1)]);
+    universe.overlay(&diagonal, 0, u32::MAX);
+    universe.set_cells(&[(4, 0), (0, u32::MAX)]);
+    assert_eq!(universe.live_cells(), vec![(3, 3)]);
+
+    assert_eq!(Universe::pattern_names()[Pattern::Glider as usize], "Glider");
+}
+
+#[test]
+fn test_touches_edge() {
+    let mut universe = Universe::from_cells(6, 6, &[(2, 2), (3, 3)]);
+    assert!(!universe.touches_edge());
+
+    universe.set_cells(&[(3, 5)]);
+    assert!(universe.touches_edge());
+}
+
+#[test]
+fn test_downsample_uneven_edges() {
+    let universe = Universe::from_cells(5, 3, &[(0, 0), (1, 1), (0, 4), (2, 4)]);
+    assert_eq!(universe.downsample(2), vec![2, 0, 1, 0, 0, 1]);
+}
+
+#[test]
+fn test_trajectory_frames() {
+    let mut universe = Universe::from_cells(3, 3, &[(0, 0)]);
+    let frames = universe.trajectory(2);
+    assert_eq!(frames.len(), 2 * 9);
+    assert!(frames.iter().all(|&cell| cell == 0));
+    assert_eq!(universe.generation(), 2);
+}
+
+#[test]
+fn test_evolve_with_rule() {
+    // A block is still under HighLife, B36/S23, as no dead cell around it has
+    // three or six live neighbours.
+    let universe = Universe::from_cells(6, 6, &[(2, 2), (2, 3), (3, 2), (3, 3)]);
+    let evolved = universe.evolve_with_rule("B36/S23", 3).unwrap();
+    assert_eq!(evolved.live_cells(), universe.live_cells());
+    assert_eq!(evolved.rule(), "B36/S23");
+    assert_eq!(universe.rule(), "B3/S23");
+
+    // Under B3/S, nothing survives, so a block dies immediately.
+    let evolved = universe.evolve_with_rule("B3/S", 1).unwrap();
+    assert_eq!(evolved.population(), 0);
+    assert_eq!(universe.population(), 4);
+}
+
+#[test]
+fn test_double_transpose_is_identity() {
+    let original = Universe::new_seeded(7, 4, 11);
+    let mut universe = original.clone();
+
+    universe.transpose();
+    assert_eq!((universe.width(), universe.height()), (4, 7));
+    universe.transpose();
+    assert_eq!((universe.width(), universe.height()), (7, 4));
+    assert_eq!(universe.to_vec_bool(), original.to_vec_bool());
+}
+
+#[test]
+fn test_detect_period() {
+    let mut blinker = Universe::from_cells(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(blinker.detect_period(10), Some(2));
+    assert_eq!(blinker.live_cells(), vec![(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(blinker.generation(), 0);
+
+    let mut block = Universe::from_cells(5, 5, &[(1, 1), (1, 2), (2, 1), (2, 2)]);
+    assert_eq!(block.detect_period(10), Some(1));
+
+    // A glider on an 8x8 torus only comes back after crossing the board,
+    // 32 generations later.
+    let mut glider = Universe::from_cells(8, 8, &[]);
+    glider.insert_pattern(Pattern::Glider, 2, 2);
+    assert_eq!(glider.detect_period(31), None);
+    assert_eq!(glider.detect_period(32), Some(32));
+}
+
+#[test]
+fn test_try_get_index() {
+    let universe = Universe::from_cells(4, 3, &[(2, 3)]);
+    assert_eq!(universe.try_get_index(2, 3), Some(11));
+    assert_eq!(universe.try_get_index(3, 0), None);
+    assert_eq!(universe.try_get_index(0, 4), None);
+    assert!(universe.get_cell(2, 3).unwrap());
+}
+
+#[test]
+fn test_set_cell_out_of_bounds() {
+    let mut universe = Universe::from_cells(4, 3, &[]);
+    let error = universe.set_cell(0, 4, true).unwrap_err();
+    assert_eq!(error.to_string(), "cell (0, 4) is outside of a 4x3 universe");
+    assert!(universe.toggle_cell(3, 0).is_err());
+    assert_eq!(universe.population(), 0);
+}
+
+#[test]
+fn test_invalid_input_errors() {
+    let mut universe = Universe::from_cells(4, 3, &[]);
+    assert_eq!(universe.set_rule("B9/S23"), Err(Error::InvalidRule("B9/S23".to_string())));
+    assert!(universe.set_pattern_at(1, 1, &[1, 0, 1]).is_err());
+    assert!(universe.hamming_distance(&Universe::from_cells(3, 4, &[])).is_err());
+    assert!(Universe::new_gun_and_eater(39, 26).is_err());
+    assert_eq!(
+        Universe::from_bytes(&universe.to_bytes()[..6]).err(),
+        Some(Error::InvalidData("truncated serialized universe"))
+    );
+}
+
+#[test]
+fn test_neighbor_histogram() {
+    let blinker = Universe::from_cells(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(blinker.neighbor_histogram(), [0, 2, 1, 0, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn test_tick_until_repeat() {
+    let mut blinker = Universe::from_cells(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(blinker.tick_until_repeat(10), Some(2));
+
+    // An R-pentomino keeps changing for far longer than 20 generations.
+    let mut r_pentomino = Universe::from_cells(64, 64, &[]);
+    r_pentomino.insert_pattern(Pattern::RPentomino, 30, 30);
+    assert_eq!(r_pentomino.tick_until_repeat(20), None);
+    assert_eq!(r_pentomino.generation(), 20);
+}
+
+#[test]
+fn test_live_cells_flat() {
+    let universe = Universe::from_cells(4, 4, &[(3, 0), (1, 2)]);
+    assert_eq!(universe.live_cells_flat(), vec![1, 2, 3, 0]);
+    assert_eq!(universe.live_cells_flat_len(), 4);
+}
+
+#[test]
+fn test_last_tick_births_and_deaths() {
+    let mut blinker = Universe::from_cells(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    blinker.tick();
+    assert_eq!((blinker.last_tick_births(), blinker.last_tick_deaths()), (2, 2));
+
+    let mut block = Universe::from_cells(5, 5, &[(1, 1), (1, 2), (2, 1), (2, 2)]);
+    block.tick();
+    assert_eq!((block.last_tick_births(), block.last_tick_deaths()), (0, 0));
+}
+
+#[test]
+fn test_gun_and_eater_equilibrium() {
+    let mut universe = Universe::new_gun_and_eater(48, 32).unwrap();
+    for _ in 0..31 {
+        universe.tick();
+    }
+    let settled = universe.to_vec_bool();
+
+    for cycle in 0..5 {
+        for _ in 0..30 {
+            universe.tick();
+        }
+        assert_eq!(universe.to_vec_bool(), settled, "cycle {}", cycle);
+    }
+
+    for _ in 0..29 {
+        universe.tick();
+    }
+    assert_eq!(universe.generation() % 30, 0);
+    assert_eq!(universe.population(), GUN_AND_EATER_POPULATION);
+}
+
+#[test]
+fn test_hamming_distance() {
+    let a = Universe::from_cells(7, 7, &[(0, 0), (3, 3), (6, 6)]);
+    let b = Universe::from_cells(7, 7, &[(0, 0), (3, 4), (6, 6)]);
+    assert_eq!(a.hamming_distance(&b).unwrap(), 2);
+    assert_eq!(a.hamming_distance(&a).unwrap(), 0);
+}
+
+#[test]
+fn test_complement() {
+    let universe = Universe::from_cells(3, 3, &[(0, 0), (1, 1)]);
+    let complement = universe.complement();
+    assert_eq!(complement.population(), 7);
+    assert!(!complement.get_cell(0, 0).unwrap() && complement.get_cell(0, 1).unwrap());
+    assert_eq!(universe.population(), 2);
+    assert!(complement.complement().same_cells(&universe));
+}
+
+#[test]
+fn test_incremental_tick_matches_naive() {
+    let mut rng = utils::Rng::new(7);
+    for _ in 0..40 {
+        let width = 1 + (rng.next_u64() % 12) as u32;
+        let height = 1 + (rng.next_u64() % 12) as u32;
+        let density = rng.next_f64();
+        let cells: Vec<bool> = (0..width * height).map(|_| rng.next_f64() < density).collect();
+        let mut universe = Universe::from_vec_bool(width, height, &cells);
+
+        for tick in 0..30 {
+            // Change the rule now and then, and edit cells between ticks.
+            if tick % 10 == 9 {
+                universe.set_rule(["B36/S23", "B2/S", "B3/S23"][tick / 10]).unwrap();
+            }
+            if rng.next_f64() < 0.3 {
+                let row = (rng.next_u64() % height as u64) as u32;
+                let col = (rng.next_u64() % width as u64) as u32;
+                universe.toggle_cell(row, col).unwrap();
+            }
+
+            let expected = universe.next_generation();
+            universe.tick();
+            assert!(universe.same_cells(&Universe::from_bitset(width, height, expected.cells)));
+            assert_eq!(universe.last_tick_births(), expected.births);
+            assert_eq!(universe.last_tick_deaths(), expected.deaths);
+        }
+    }
+}
+
+#[test]
+fn test_track_cluster_follows_glider_across_edges() {
+    // A glider moves one cell down and to the right every 4 generations, so
+    // after 64 it has gone once around this universe, well clear of the
+    // block.
+    let mut universe = Universe::from_cells(16, 16, &[]);
+    universe.insert_pattern(Pattern::Glider, 4, 4);
+    universe.insert_pattern(Pattern::Block, 4, 12);
+
+    let trajectory = universe.track_cluster(6, 6, 64);
+    assert_eq!(trajectory.len(), 65);
+    let (start_row, start_col) = trajectory[0];
+    assert_eq!(trajectory[4], (start_row + 1.0, start_col + 1.0));
+    assert_eq!(trajectory[64], (start_row + 16.0, start_col + 16.0));
+}
+
+#[test]
+fn test_blinker_sites() {
+    let mut universe = Universe::from_cells(12, 12, &[]);
+    universe.insert_pattern(Pattern::Blinker, 0, 5);
+    universe.insert_pattern(Pattern::Block, 8, 8);
+    // A vertical blinker wrapping around the left edge.
+    universe.set_cells(&[(5, 0), (6, 0), (7, 0)]);
+    // Three cells in a row touching another one are not a blinker.
+    universe.set_cells(&[(4, 5), (4, 6), (4, 7), (3, 8)]);
+
+    assert_eq!(universe.blinker_sites(), vec![(0, 6), (6, 0)]);
+    universe.tick();
+    let sites = universe.blinker_sites();
+    assert!(sites.contains(&(0, 6)) && sites.contains(&(6, 0)));
+}
+
+#[test]
+fn test_narrow_universe_neighbors() {
+    // On a universe 2 cells wide, a cell's left and right neighbours are the
+    // same cell, and on one 1 cell tall, its row is its own row above and
+    // below, so it only has its other cell as a neighbour.
+    let universe = Universe::from_cells(2, 1, &[(0, 1)]);
+    assert_eq!(universe.live_neighbor_count(0, 0), 1);
+    assert_eq!(universe.live_neighbor_count(0, 1), 0);
+
+    let mut universe = Universe::from_cells(3, 3, &[(0, 0), (1, 1)]);
+    assert_eq!(universe.live_neighbor_count(0, 1), 2);
+    universe.set_width(1);
+    universe.set_cell(0, 0, true).unwrap();
+    universe.set_cell(1, 0, true).unwrap();
+    assert_eq!(universe.live_neighbor_count(0, 0), 1);
+}
+
+#[test]
+fn test_render_emoji() {
+    let mut universe = Universe::from_cells(3, 2, &[(0, 1), (1, 2)]);
+    assert_eq!(universe.render_emoji(), "⬜⬛⬜\n⬜⬜⬛\n");
+    universe.set_emoji("🟩", "⬛");
+    assert_eq!(universe.render_emoji(), "⬛🟩⬛\n⬛⬛🟩\n");
+}
+
+#[test]
+fn test_rewind() {
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    universe.insert_pattern(Pattern::Glider, 1, 1);
+    let start = universe.clone();
+    universe.set_undo_limit(3);
+
+    for _ in 0..5 {
+        universe.tick();
+    }
+    let after_two = {
+        let mut universe = start.clone();
+        universe.tick();
+        universe.tick();
+        universe
+    };
+
+    assert_eq!(universe.rewind(2), 2);
+    assert_eq!(universe.generation(), 3);
+    assert_eq!(universe.rewind(5), 1);
+    assert_eq!(universe.generation(), 2);
+    assert!(universe.same_cells(&after_two));
+    assert_eq!(universe.population_history(), after_two.population_history());
+    assert_eq!(universe.rewind(1), 0);
+}
+
+#[test]
+fn test_rewind_past_resize() {
+    let mut universe = Universe::from_cells(4, 4, &[(1, 0), (1, 1), (1, 2)]);
+    universe.set_undo_limit(3);
+    let start = universe.clone();
+    universe.tick();
+    universe.resize_centered(8, 8);
+    assert_eq!(universe.rewind(1), 1);
+    assert_eq!((universe.width(), universe.height()), (4, 4));
+    assert!(universe.same_cells(&start));
+    universe.set_cell(3, 3, true).unwrap();
+    assert!(universe.set_cell(7, 7, true).is_err());
+
+    let mut universe = Universe::from_cells(8, 4, &[(0, 7)]);
+    universe.set_undo_limit(1);
+    universe.tick();
+    universe.transpose();
+    assert_eq!(universe.rewind(1), 1);
+    assert_eq!((universe.width(), universe.height()), (8, 4));
+    assert_eq!(universe.live_cells(), vec![(0, 7)]);
+}
+
+#[test]
+fn test_is_periodic() {
+    let mut universe = Universe::from_cells(16, 16, &[]);
+    universe.insert_pattern(Pattern::Block, 1, 1);
+    universe.insert_pattern(Pattern::Blinker, 8, 8);
+    assert!(universe.is_periodic(2));
+    assert_eq!(universe.generation(), 0);
+
+    universe.insert_pattern(Pattern::Glider, 4, 10);
+    assert!(!universe.is_periodic(30));
+}
+
+#[test]
+fn test_tile_pattern() {
+    let mut block = Universe::from_cells(2, 2, &[]);
+    block.insert_pattern(Pattern::Block, 0, 0);
+
+    let mut universe = Universe::from_cells(10, 7, &[(6, 9)]);
+    universe.tile_pattern(&block, 3, 4);
+    // Blocks at rows 0, 3 and 6 and columns 0, 4 and 8, the last row of
+    // them clipped to their top half.
+    assert_eq!(universe.population(), 4 * 6 + 2 * 3);
+    assert!(universe.get_cell(6, 9).unwrap());
+    assert!(universe.get_cell(3, 5).unwrap() && !universe.get_cell(3, 6).unwrap());
+}
+
+#[test]
+fn test_preview_cell() {
+    let universe = Universe::from_cells(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(universe.preview_cell(2, 2), Ok((2, true, true)));
+    assert_eq!(universe.preview_cell(2, 1), Ok((1, true, false)));
+    assert_eq!(universe.preview_cell(1, 2), Ok((3, false, true)));
+    assert_eq!(universe.preview_cell_flat(1, 2), Ok(vec![3, 0, 1]));
+    assert!(universe.preview_cell(5, 0).is_err());
+}
+
+#[test]
+fn test_seed_dead_cells() {
+    let mut universe = Universe::from_cells(16, 16, &[]);
+    universe.insert_pattern(Pattern::Block, 7, 7);
+    let block = universe.clone();
+
+    universe.seed_dead_cells(0.25, Some(3));
+    assert!(block.live_cells().iter().all(|&(row, col)| universe.get_cell(row, col).unwrap()));
+    let population = universe.population();
+    assert!(population > 4 + 30 && population < 4 + 100, "{}", population);
+
+    let mut again = block.clone();
+    again.seed_dead_cells(0.25, Some(3));
+    assert!(again.same_cells(&universe));
+
+    again.seed_dead_cells(2.0, Some(3));
+    assert_eq!(again.population(), 256);
+}
+
+#[test]
+fn test_stable_for() {
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    universe.insert_pattern(Pattern::Block, 1, 1);
+    assert_eq!(universe.stable_for(), 0);
+    universe.tick();
+    universe.tick();
+    assert_eq!(universe.stable_for(), 2);
+
+    universe.insert_pattern(Pattern::Blinker, 5, 3);
+    assert_eq!(universe.stable_for(), 0);
+    universe.tick();
+    assert_eq!(universe.stable_for(), 0);
+
+    universe.clear_all_cells();
+    universe.tick();
+    assert_eq!(universe.stable_for(), 1);
+    universe.set_width(9);
+    assert_eq!(universe.stable_for(), 0);
+    universe.tick();
+    assert_eq!(universe.stable_for(), 1);
+    universe.transpose();
+    assert_eq!(universe.stable_for(), 0);
+}
+
+#[test]
+fn test_life24_block_is_stable() {
+    // Every cell of a 3x3 block sees the 8 others within its 5x5
+    // neighbourhood, so it survives, while no dead cell sees more than 6 of
+    // them, too few to be born.
+    let mut universe = Universe::from_cells(12, 12, &[]);
+    for row in 4..7 {
+        for col in 4..7 {
+            universe.set_cell(row, col, true).unwrap();
+        }
+    }
+    universe.set_rule("Life24").unwrap();
+    assert_eq!(universe.rule(), "Life24");
+    assert_eq!(universe.detect_period(1), Some(1));
+
+    // A 2x2 block, fine under Conway's rule, dies out.
+    let mut universe = Universe::from_cells(12, 12, &[]);
+    universe.insert_pattern(Pattern::Block, 4, 4);
+    universe.set_rule("Life24").unwrap();
+    universe.tick();
+    assert_eq!(universe.population(), 0);
+
+    universe.set_rule("B3/S23").unwrap();
+    assert_eq!(universe.rule(), "B3/S23");
+}
+
+#[test]
+fn test_dead_boundary() {
+    let mut universe = Universe::from_cells(8, 8, &[(0, 2), (0, 3), (0, 4)]);
+    let mut toroidal = universe.clone();
+    universe.set_boundary(Boundary::Dead);
+
+    // A blinker on the top edge would be born a cell past it, which is lost
+    // and the rest dies out.
+    universe.tick();
+    assert_eq!(universe.live_cells(), vec![(0, 3), (1, 3)]);
+    assert_eq!(universe.escaped_count(), 1);
+    universe.tick();
+    assert_eq!(universe.population(), 0);
+
+    toroidal.tick();
+    assert_eq!(toroidal.live_cells(), vec![(0, 3), (1, 3), (7, 3)]);
+    assert_eq!(toroidal.escaped_count(), 0);
+}
+
+#[test]
+fn test_chained_edits() {
+    let mut universe = Universe::from_cells(8, 8, &[(7, 7)]);
+    universe
+        .clear()
+        .fill_rect(6, 6, 4, 4, true)
+        .fill_rect(7, 7, 1, 1, false)
+        .draw_line(0, 0, 2, 5)
+        .set_cells(&[(4, 0)]);
+
+    assert_eq!(
+        universe.live_cells(),
+        vec![(0, 0), (0, 1), (1, 2), (1, 3), (2, 4), (2, 5), (4, 0), (6, 6), (6, 7), (7, 6)]
+    );
+
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    universe.draw_line(5, 1, 1, 1).insert_glider(4, 4);
+    assert_eq!(universe.population(), 5 + 5);
+}
+
+#[test]
+fn test_row_spectrum() {
+    let universe = Universe::from_cells(8, 2, &[(1, 0), (1, 2), (1, 4), (1, 6)]);
+    let spectrum = universe.row_spectrum(1).unwrap();
+    for (k, magnitude) in spectrum.iter().enumerate() {
+        let expected = if k == 0 || k == 4 { 4.0 } else { 0.0 };
+        assert!((magnitude - expected).abs() < 1e-9, "{}: {}", k, magnitude);
+    }
+    assert_eq!(universe.row_spectrum(0).unwrap(), vec![0.0; 8]);
+    assert!(universe.row_spectrum(2).is_err());
+}
+
+#[test]
+fn test_rule_region() {
+    // Two blinkers, the right one in a region where nothing survives.
+    let mut universe = Universe::from_cells(12, 6, &[(2, 1), (2, 2), (2, 3), (2, 7), (2, 8), (2, 9)]);
+    universe.set_rule_region("B3/S", 0, 6, 6, 100).unwrap();
+    universe.tick();
+    assert_eq!(universe.live_cells(), vec![(1, 2), (1, 8), (2, 2), (3, 2), (3, 8)]);
+
+    assert!(universe.set_rule_region("B3/S9", 0, 0, 1, 1).is_err());
+    universe.clear_rule_region();
+    universe.tick();
+    assert_eq!(universe.population(), 3);
+}
+
+#[test]
+fn test_is_equivalent() {
+    let mut universe = Universe::from_cells(10, 10, &[]);
+    universe.insert_pattern(Pattern::Glider, 1, 1);
+
+    // The same glider at another position, reflected and rotated.
+    let mut other = Universe::from_cells(12, 8, &[]);
+    other.set_cells(&[(5, 8), (6, 6), (6, 8), (7, 7), (7, 8)]);
+    assert!(universe.is_equivalent(&other));
+    assert!(other.is_equivalent(&universe));
+
+    other.set_cells(&[(0, 0)]);
+    assert!(!universe.is_equivalent(&other));
+    let mut r_pentomino = Universe::from_cells(10, 10, &[]);
+    r_pentomino.insert_pattern(Pattern::RPentomino, 3, 3);
+    assert!(!universe.is_equivalent(&r_pentomino));
+    assert!(Universe::from_cells(3, 3, &[]).is_equivalent(&Universe::from_cells(5, 5, &[])));
+}
+
+#[test]
+fn test_cluster_colors() {
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    universe.insert_pattern(Pattern::Block, 0, 0);
+    universe.insert_pattern(Pattern::Blinker, 4, 3);
+    // Part of the block's group across the edge.
+    universe.set_cells(&[(7, 7)]);
+
+    let colors = universe.cluster_colors();
+    assert_eq!(colors.len(), 64);
+    let block = colors[0];
+    let blinker = colors[4 * 8 + 3];
+    assert!(block != 0 && blinker != 0 && block != blinker);
+    assert_eq!(colors[7 * 8 + 7], block);
+    assert_eq!(colors.iter().filter(|&&color| color == blinker).count(), 3);
+    assert_eq!(colors.iter().filter(|&&color| color == 0).count(), 64 - 8);
+    assert_eq!(universe.cluster_colors(), colors);
+}
+
+#[test]
+fn test_run_until_population() {
+    // Under Life without death, B3/S012345678, the R-pentomino only grows.
+    let mut universe = Universe::from_cells(32, 32, &[]);
+    universe.insert_pattern(Pattern::RPentomino, 14, 14);
+    universe.set_rule("B3/S012345678").unwrap();
+    let generation = universe.run_until_population(20, 100).unwrap();
+    assert!(universe.population() >= 20);
+    assert_eq!(universe.generation(), generation);
+    assert_eq!(universe.run_until_population(20, 100), Some(generation));
+
+    // A block never grows, and the search stops after a tick.
+    let mut block = Universe::from_cells(8, 8, &[]);
+    block.insert_pattern(Pattern::Block, 2, 2);
+    assert_eq!(block.run_until_population(5, 100), None);
+    assert_eq!(block.generation(), 1);
+}
+
+#[test]
+fn test_edge_populations() {
+    let universe = Universe::from_cells(6, 5, &[(0, 0), (0, 3), (2, 5), (4, 5), (2, 2)]);
+    assert_eq!(universe.edge_populations(), (2, 1, 1, 2));
+    assert_eq!(Universe::from_cells(6, 5, &[(2, 2)]).edge_populations(), (0, 0, 0, 0));
+}
+
+#[test]
+fn test_activity_map() {
+    // A horizontal blinker turns vertical, keeping its middle cell.
+    let mut universe = Universe::from_cells(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    let map = universe.activity_map();
+    assert_eq!(universe.generation(), 1);
+    let at = |row: u32, col: u32| map[(row * 5 + col) as usize];
+    assert_eq!((at(2, 1), at(2, 3)), (2, 2));
+    assert_eq!((at(1, 2), at(3, 2)), (1, 1));
+    assert_eq!(at(2, 2), 3);
+    assert_eq!(map.iter().filter(|&&activity| activity == 0).count(), 20);
+
+    // The buffer behind the cells is not shrunk with the board.
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    universe.set_height(4);
+    assert_eq!(universe.activity_map().len(), 32);
+}
+
+#[test]
+fn test_new_hardcoded() {
+    let universe = Universe::new_hardcoded(64, 64);
+    assert!(universe.same_cells(&Universe::hardcoded_64_by_64()));
+
+    let universe = Universe::new_hardcoded(10, 3);
+    let expected: Vec<bool> = (0..30).map(|i| i % 2 == 0 || i % 7 == 0).collect();
+    assert_eq!((0..30).map(|i| universe.get_cells()[i]).collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn test_count_next_live() {
+    let mut universe = Universe::new_hardcoded(16, 16);
+    for _ in 0..5 {
+        let expected = universe.count_next_live();
+        universe.tick();
+        assert_eq!(universe.population(), expected);
+    }
+}
+
+#[test]
+fn test_scatter_pattern() {
+    let mut universe = Universe::from_cells(32, 32, &[]);
+    assert_eq!(universe.scatter_pattern(Pattern::Glider, 10, 7), 10);
+    assert_eq!(universe.population(), 50);
+    assert_eq!(universe.clusters().len(), 10);
+
+    let mut again = Universe::from_cells(32, 32, &[]);
+    again.scatter_pattern(Pattern::Glider, 10, 7);
+    assert!(again.same_cells(&universe));
+
+    // There is only room for a single block, with its surrounding ring.
+    let mut small = Universe::from_cells(4, 4, &[]);
+    assert_eq!(small.scatter_pattern(Pattern::Block, 3, 1), 1);
+    assert_eq!(small.population(), 4);
+}
+
+#[test]
+fn test_ash_census() {
+    let mut universe = Universe::from_cells(24, 24, &[]);
+    universe.insert_pattern(Pattern::Block, 1, 1);
+    universe.insert_pattern(Pattern::Block, 1, 10);
+    universe.insert_pattern(Pattern::Blinker, 10, 1);
+    universe.insert_pattern(Pattern::Beehive, 10, 10);
+    universe.insert_pattern(Pattern::Boat, 18, 18);
+    // A glider is not ash, and never settles, so the census is taken after
+    // the limit.
+    universe.insert_pattern(Pattern::Glider, 17, 2);
+
+    let census = universe.ash_census(3);
+    let count = |name: &str| census.iter().find(|(n, _)| n == name).map(|&(_, count)| count);
+    assert_eq!(count("Block"), Some(2));
+    assert_eq!(count("Blinker"), Some(1));
+    assert_eq!(count("Beehive"), Some(1));
+    assert_eq!(count("Loaf"), Some(0));
+    assert_eq!(count("Boat"), Some(1));
+    assert_eq!(census.len(), 5);
+}
+
+#[test]
+fn test_bit_layout() {
+    assert_eq!(Universe::bit_layout(), 1 << 8 | 32);
+
+    // Cell 33 is the second bit of the second word.
+    let universe = Universe::from_cells(10, 4, &[(3, 3)]);
+    assert_eq!(universe.get_cells().as_slice(), &[0, 1 << 1]);
+}
+
+#[test]
+fn test_toggle_rect() {
+    let original = Universe::new_hardcoded(12, 10);
+    let mut universe = Universe::new_hardcoded(12, 10);
+
+    universe.toggle_rect(2, 3, 4, 5);
+    assert_eq!(universe.hamming_distance(&original), Ok(20));
+    assert_ne!(universe.get_cell(2, 3), original.get_cell(2, 3));
+    assert_eq!(universe.get_cell(2, 8), original.get_cell(2, 8));
+    universe.toggle_rect(2, 3, 4, 5);
+    assert!(universe.same_cells(&original));
+
+    // Whole rows, clipped at the bottom.
+    universe.toggle_rect(8, 0, 5, 20);
+    assert_eq!(universe.hamming_distance(&original), Ok(24));
+    universe.toggle_rect(8, 0, 5, 20);
+    assert!(universe.same_cells(&original));
+}
+
+#[test]
+fn test_heat_map() {
+    // The ends of a blinker change every tick, its middle never does.
+    let mut universe = Universe::from_cells(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    let heat = universe.heat_map(4);
+    assert_eq!(universe.generation(), 4);
+    let at = |row: u32, col: u32| heat[(row * 5 + col) as usize];
+    assert_eq!([at(2, 1), at(2, 3), at(1, 2), at(3, 2)], [4; 4]);
+    assert_eq!(at(2, 2), 0);
+    assert_eq!(heat.iter().sum::<u32>(), 16);
+}
+
+#[test]
+fn test_resize_centered() {
+    // A glider in the middle of a 7x7 universe.
+    let mut universe = Universe::from_cells(7, 7, &[]);
+    universe.insert_pattern(Pattern::Glider, 2, 2);
+
+    universe.resize_centered(11, 13);
+    let mut expected = Universe::from_cells(11, 13, &[]);
+    expected.insert_pattern(Pattern::Glider, 5, 4);
+    assert!(universe.same_cells(&expected));
+
+    universe.resize_centered(5, 5);
+    let mut expected = Universe::from_cells(5, 5, &[]);
+    expected.insert_pattern(Pattern::Glider, 1, 1);
+    assert!(universe.same_cells(&expected));
+
+    // Cropped through the glider, of which only the top cell is left.
+    universe.resize_centered(2, 2);
+    assert_eq!(universe.live_cells(), vec![(0, 1)]);
+}
+
+#[test]
+fn test_remove_single_cells() {
+    let mut universe = Universe::from_cells(8, 8, &[(0, 0), (3, 3), (3, 4), (6, 6)]);
+    assert_eq!(universe.remove_single_cells(), 2);
+    assert_eq!(universe.live_cells(), vec![(3, 3), (3, 4)]);
+    assert_eq!(universe.generation(), 0);
+    assert_eq!(universe.remove_single_cells(), 0);
+}
+
+#[test]
+fn test_neighbor_count() {
+    let universe = Universe::from_cells(5, 5, &[(0, 0), (0, 1), (4, 4), (2, 2)]);
+    assert_eq!(universe.neighbor_count(0, 0), Ok(2));
+    assert_eq!(universe.neighbor_count(1, 1), Ok(3));
+    assert_eq!(universe.neighbor_count(2, 4), Ok(0));
+    assert!(matches!(universe.neighbor_count(5, 0), Err(Error::OutOfBounds { .. })));
+}
 
-        self.set_pattern_at(row, column, &neighbourhood);
+#[test]
+fn test_new_symmetric() {
+    let mut quadrant = Universe::from_cells(4, 4, &[]);
+    quadrant.insert_pattern(Pattern::Glider, 0, 0);
+    let mut universe = Universe::new_symmetric(10, 9, &quadrant);
+    assert_eq!(universe.population(), 20);
 
+    let mirrored = |universe: &Universe| {
+        universe.live_cells().iter().all(|&(row, col)| {
+            universe.get_cell(8 - row, col) == Ok(true) && universe.get_cell(row, 9 - col) == Ok(true)
+        })
+    };
+    assert!(mirrored(&universe));
+    for _ in 0..10 {
+        universe.tick();
+        assert!(mirrored(&universe));
     }
 
-    fn new_traffic_light_at(&mut self, row: u32, column: u32) {
-        let neighbourhood = [
-            1, 1, 1,
-            1, 0, 1,
-            1, 1, 1,
-        ];
+    // The middle row of an odd height is shared.
+    let universe = Universe::new_symmetric(4, 3, &Universe::from_cells(4, 4, &[(1, 0), (2, 0)]));
+    assert_eq!(universe.live_cells(), vec![(1, 0), (1, 3)]);
+}
+
+#[test]
+fn test_max_neighbor_count() {
+    assert_eq!(Universe::from_cells(6, 6, &[]).max_neighbor_count(), 0);
+
+    // The dead cell in the middle of a ring of 8.
+    let mut universe = Universe::from_cells(6, 6, &[]);
+    universe.fill_rect(1, 1, 3, 3, true);
+    universe.set_cell(2, 2, false).unwrap();
+    assert_eq!(universe.max_neighbor_count(), 8);
+}
+
+#[test]
+fn test_frozen_block_stops_glider() {
+    let run = |freeze: bool| {
+        let mut universe = Universe::from_cells(16, 16, &[]);
+        universe.insert_pattern(Pattern::Glider, 0, 0);
+        universe.insert_pattern(Pattern::Block, 6, 6);
+        if freeze {
+            for &(row, col) in &[(6, 6), (6, 7), (7, 6), (7, 7)] {
+                universe.freeze_cell(row, col).unwrap();
+            }
+        }
+        for _ in 0..40 {
+            universe.tick();
+        }
+        universe
+    };
+
+    let block = |universe: &Universe| universe.matches_template(5, 5, 4, 4, &[(1, 1), (1, 2), (2, 1), (2, 2)]);
+    assert!(!block(&run(false)));
+    let frozen = run(true);
+    assert!((6..8).all(|row| (6..8).all(|col| frozen.get_cell(row, col) == Ok(true))));
+
+    let mut universe = Universe::from_cells(4, 4, &[(1, 1)]);
+    universe.freeze_cell(1, 1).unwrap();
+    universe.tick();
+    assert_eq!(universe.population(), 1);
+    universe.unfreeze_cell(1, 1).unwrap();
+    universe.tick();
+    assert_eq!(universe.population(), 0);
+    assert!(universe.freeze_cell(4, 0).is_err());
+}
+
+#[test]
+fn test_influence_region() {
+    let mut universe = Universe::from_cells(20, 10, &[]);
+    assert_eq!(universe.influence_region(3), (0, 0, 0, 0));
+
+    universe.insert_pattern(Pattern::Glider, 1, 8);
+    assert_eq!(universe.influence_region(0), (1, 8, 3, 3));
+    assert_eq!(universe.influence_region(2), (9, 6, 7, 7));
+    assert_eq!(universe.influence_region(4), (0, 4, 10, 11));
+
+    universe.set_boundary(Boundary::Dead);
+    assert_eq!(universe.influence_region(2), (0, 6, 6, 7));
+}
+
+#[test]
+fn test_tick_delta() {
+    let mut universe = Universe::from_cells(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(universe.tick_delta(), vec![7, 11, 13, 17]);
+    assert_eq!(universe.generation(), 1);
+    assert_eq!(universe.tick_delta(), vec![7, 11, 13, 17]);
+}
+
+#[test]
+fn test_apply_delta_replays_a_run() {
+    let mut universe = Universe::new_hardcoded(12, 12);
+    let keyframe = universe.to_bytes();
+    let deltas: Vec<Vec<u32>> = (0..8).map(|_| universe.tick_delta()).collect();
 
-        self.set_pattern_at(row, column, &neighbourhood);
+    let mut replayed = Universe::from_bytes(&keyframe).unwrap();
+    for delta in &deltas {
+        replayed.apply_delta(delta).unwrap();
     }
+    assert!(replayed.same_cells(&universe));
 
-    pub fn new_pre_pulsar_at(&mut self, row: u32, column: u32) {
-        self.new_traffic_light_at(row, column + self.width() - 3);
-        self.new_traffic_light_at(row, column + 3);
+    let error = replayed.apply_delta(&[3, 144]).unwrap_err();
+    assert_eq!(error, Error::OutOfBounds { row: 12, column: 0, width: 12, height: 12 });
+    assert!(replayed.same_cells(&universe));
+}
+
+#[test]
+fn test_distinct_states() {
+    let mut block = Universe::from_cells(8, 8, &[(2, 2), (2, 3), (3, 2), (3, 3)]);
+    assert_eq!(block.distinct_states(10), 1);
+    let mut blinker = Universe::from_cells(8, 8, &[(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(blinker.distinct_states(10), 2);
+    assert_eq!(blinker.generation(), 10);
+
+    // A glider goes through 4 phases at each of 8 positions along its way.
+    let mut glider = Universe::from_cells(8, 8, &[]);
+    glider.insert_pattern(Pattern::Glider, 0, 0);
+    assert_eq!(glider.distinct_states(100), 32);
+}
+
+#[test]
+fn test_place_random() {
+    let mut universe = Universe::from_cells(10, 10, &[]);
+    assert!(universe.place_random(Pattern::Glider, 3));
+    assert_eq!(universe.population(), 5);
+
+    let mut again = Universe::from_cells(10, 10, &[]);
+    again.place_random(Pattern::Glider, 3);
+    assert!(again.same_cells(&universe));
+
+    let mut full = Universe::from_cells(4, 4, &[]);
+    full.fill_rect(0, 0, 4, 4, true);
+    assert!(!full.place_random(Pattern::Block, 3));
+    assert!(!Universe::from_cells(2, 2, &[]).place_random(Pattern::Glider, 3));
+}
+
+#[test]
+fn test_parities() {
+    let mut universe = Universe::from_cells(4, 3, &[(0, 0), (0, 3), (1, 1), (2, 1), (2, 2)]);
+    assert_eq!(universe.row_parities(), vec![false, true, false]);
+    assert_eq!(universe.column_parities(), vec![true, false, true, true]);
+
+    universe.transpose();
+    assert_eq!(universe.row_parities(), vec![true, false, true, true]);
+    assert_eq!(universe.column_parities(), vec![false, true, false]);
+}
+
+#[test]
+fn test_growth_trend() {
+    let mut block = Universe::from_cells(8, 8, &[(2, 2), (2, 3), (3, 2), (3, 3)]);
+    assert_eq!(block.growth_trend(10), 0.0);
+    assert_eq!(block.generation(), 10);
+    assert_eq!(block.growth_trend(0), 0.0);
+
+    // The gun adds a glider of 5 cells every 30 generations.
+    let mut gun = Universe::from_cells(80, 80, &[]);
+    gun.insert_pattern(Pattern::GosperGliderGun, 1, 1);
+    let trend = gun.growth_trend(120);
+    assert!(trend > 0.1 && trend < 0.25, "{}", trend);
+
+    // Three lone cells die out at once.
+    let mut dying = Universe::from_cells(8, 8, &[(0, 0), (3, 3), (6, 0)]);
+    assert!(dying.growth_trend(4) < 0.0);
+}
+
+#[test]
+fn test_from_rows() {
+    let glider = Universe::from_rows(&[".#.", "..#", "###"], '#');
+    let mut expected = Universe::from_cells(3, 3, &[]);
+    expected.insert_pattern(Pattern::Glider, 0, 0);
+    assert!(glider.same_cells(&expected));
+
+    let ragged = Universe::from_rows(&["o", "", "boo"], 'o');
+    assert_eq!((ragged.width(), ragged.height()), (3, 3));
+    assert_eq!(ragged.live_cells(), vec![(0, 0), (2, 1), (2, 2)]);
+}
+
+#[test]
+fn test_combine_rules() {
+    assert_eq!(combine_rules("B3/S23", "B36/S23", RuleOp::Union), Ok("B36/S23".to_string()));
+    assert_eq!(combine_rules("B3/S23", "B36/S23", RuleOp::Intersection), Ok("B3/S23".to_string()));
+    assert_eq!(combine_rules("B2/S", "b3/s23", RuleOp::Intersection), Ok("B/S".to_string()));
+    assert_eq!(combine_rules("B3/S23", "Life24", RuleOp::Union), Err(Error::InvalidRule("Life24".to_string())));
+}
+
+#[test]
+fn test_ticks_until_cell_dead() {
+    // The ends of a blinker die on the first tick, its middle never does.
+    let mut universe = Universe::from_cells(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(universe.ticks_until_cell_dead(2, 1, 10), Ok(Some(1)));
+    assert_eq!(universe.ticks_until_cell_dead(0, 0, 10), Ok(Some(1)));
+    assert_eq!(universe.ticks_until_cell_dead(2, 2, 10), Ok(None));
+    assert_eq!(universe.generation(), 11);
+    assert!(universe.ticks_until_cell_dead(0, 5, 10).is_err());
+}
+
+#[test]
+fn test_canonical_form() {
+    let mut universe = Universe::from_cells(10, 10, &[]);
+    universe.insert_pattern(Pattern::Glider, 1, 2);
+    let canonical = universe.canonical_form();
+    assert!(canonical.is_equivalent(&universe));
+    assert_eq!((canonical.width(), canonical.height()), (3, 3));
+
+    // The same glider elsewhere, transposed, and one generation later.
+    let mut other = Universe::from_cells(12, 9, &[]);
+    other.insert_pattern(Pattern::Glider, 5, 6);
+    other.transpose();
+    assert!(other.canonical_form().same_cells(&canonical));
+    other.tick();
+    assert!(!other.canonical_form().same_cells(&canonical));
+
+    assert_eq!(Universe::from_cells(4, 4, &[]).canonical_form().width(), 0);
+}
+
+#[test]
+fn test_insert_pattern_wrapped() {
+    let mut universe = Universe::from_cells(6, 5, &[]);
+    universe.insert_pattern_wrapped(Pattern::Block, 1, 5);
+    assert_eq!(universe.live_cells(), vec![(1, 0), (1, 5), (2, 0), (2, 5)]);
+
+    universe.clear();
+    universe.insert_pattern_wrapped(Pattern::Block, 4, 5);
+    assert_eq!(universe.live_cells(), vec![(0, 0), (0, 5), (4, 0), (4, 5)]);
+
+    universe.clear();
+    universe.insert_pattern(Pattern::Block, 1, 5);
+    assert_eq!(universe.live_cells(), vec![(1, 5), (2, 5)]);
+}
+
+#[test]
+fn test_soup_result() {
+    let mut universe = Universe::from_cells(64, 64, &[]);
+    let signature = universe.soup_result(1, 2000).unwrap();
+    assert_eq!(signature.len(), 16);
+    assert!(signature.bytes().all(|b| b.is_ascii_hexdigit()));
+
+    let mut again = Universe::from_cells(64, 64, &[(0, 0)]);
+    assert_eq!(again.soup_result(1, 2000), Ok(signature.clone()));
+    assert!(again.same_cells(&universe));
+
+    // What is left after settling does not depend on where it lies: the
+    // same objects elsewhere, in another order, have the same signature.
+    let mut objects = Universe::from_cells(40, 40, &[]);
+    objects.insert_pattern(Pattern::Block, 2, 2);
+    objects.insert_pattern(Pattern::Blinker, 10, 10);
+    let mut moved = Universe::from_cells(40, 40, &[]);
+    moved.insert_pattern(Pattern::Blinker, 30, 3);
+    moved.insert_pattern(Pattern::Block, 20, 25);
+    assert_eq!(objects.ash_signature(), moved.ash_signature());
+    moved.tick();
+    assert_eq!(objects.ash_signature(), moved.ash_signature());
+    moved.insert_pattern(Pattern::Block, 30, 30);
+    assert_ne!(objects.ash_signature(), moved.ash_signature());
+
+    assert!(Universe::from_cells(15, 64, &[]).soup_result(1, 10).is_err());
+}
+
+#[test]
+fn test_use_seeded_rng() {
+    let run = || {
+        use_seeded_rng(42);
+        let universe = Universe::new(16, 16);
+        let mut reinitialized = Universe::from_cells(16, 16, &[]);
+        reinitialized.reinitialize_rng();
+        (universe, reinitialized)
+    };
+    let (universe, reinitialized) = run();
+    let (again, reinitialized_again) = run();
+    use_system_rng();
+
+    assert!(again.same_cells(&universe));
+    assert!(reinitialized_again.same_cells(&reinitialized));
+    assert!(!reinitialized.same_cells(&universe));
+}
+
+#[test]
+fn test_largest_cluster_size() {
+    assert_eq!(Universe::from_cells(8, 8, &[]).largest_cluster_size(), 0);
+
+    // The blinker touches the corner cell diagonally across the edges.
+    let universe = Universe::from_cells(8, 8, &[(0, 0), (7, 5), (7, 6), (7, 7), (3, 3), (3, 4)]);
+    assert_eq!(universe.largest_cluster_size(), 4);
+}
+
+#[test]
+fn test_tick_with_source() {
+    let mut universe = Universe::from_cells(8, 8, &[(3, 3)]);
+    for _ in 0..3 {
+        universe.tick_with_source(3, 3).unwrap();
+        assert_eq!(universe.live_cells(), vec![(3, 3)]);
     }
+    assert_eq!(universe.generation(), 3);
+    assert!(universe.tick_with_source(8, 0).is_err());
+    assert_eq!(universe.generation(), 3);
+}
 
-    pub fn render(&self) -> String {
-        self.to_string()
+#[test]
+fn test_outline() {
+    let mut universe = Universe::from_cells(6, 6, &[]);
+    universe.fill_rect(1, 1, 3, 4, true);
+    let outline = universe.outline();
+    let at = |row: u32, col: u32| outline[(row * 6 + col) as usize];
+    assert_eq!([at(0, 0), at(1, 1), at(2, 2), at(2, 3), at(3, 4)], [0, 1, 2, 2, 1]);
+    assert_eq!(outline.iter().filter(|&&cell| cell == 1).count(), 10);
+
+    // Filling the whole board leaves no outline, unless the edges are dead.
+    universe.fill_rect(0, 0, 6, 6, true);
+    assert!(universe.outline().iter().all(|&cell| cell == 2));
+    universe.set_boundary(Boundary::Dead);
+    assert_eq!(universe.outline().iter().filter(|&&cell| cell == 1).count(), 20);
+}
+
+#[test]
+fn test_from_halves() {
+    let left = Universe::from_rows(&["#..", "..#"], '#');
+    let right = Universe::from_rows(&[".#", "#."], '#');
+    let joined = Universe::from_halves(&left, &right).unwrap();
+    assert!(joined.same_cells(&Universe::from_rows(&["#...#", "..##."], '#')));
+
+    let stacked = Universe::from_stacked_halves(&left, &Universe::from_rows(&["##."], '#')).unwrap();
+    assert!(stacked.same_cells(&Universe::from_rows(&["#..", "..#", "##."], '#')));
+
+    let error = Universe::from_halves(&left, &Universe::from_cells(2, 3, &[])).err();
+    assert!(matches!(error, Some(Error::DimensionMismatch { other_height: 3, .. })));
+    assert!(Universe::from_stacked_halves(&left, &right).is_err());
+}
+
+#[test]
+fn test_pending_changes() {
+    let mut universe = Universe::new_hardcoded(16, 16);
+    for _ in 0..5 {
+        let pending = universe.pending_changes();
+        assert_eq!(universe.tick_delta(), pending);
     }
+    assert!(Universe::from_cells(4, 4, &[(1, 1), (1, 2), (2, 1), (2, 2)]).pending_changes().is_empty());
 }
 
-impl Universe {
-    /// Get the dead and alive values of the entire universe.
-    pub fn get_cells(&self) -> &FixedBitSet {
-        &self.cells
+#[test]
+fn test_step_elementary() {
+    // Rule 90 turns a single cell into its two neighbours, in every row.
+    let mut universe = Universe::from_rows(&["..#..", "#...."], '#');
+    universe.step_elementary(90);
+    assert!(universe.same_cells(&Universe::from_rows(&[".#.#.", ".#..#"], '#')));
+    assert_eq!(universe.generation(), 0);
+
+    // Rule 204 is the identity, and rule 0 kills everything.
+    let original = Universe::new_hardcoded(9, 4);
+    let mut universe = Universe::new_hardcoded(9, 4);
+    universe.step_elementary(204);
+    assert!(universe.same_cells(&original));
+    universe.step_elementary(0);
+    assert_eq!(universe.population(), 0);
+}
+
+#[test]
+fn test_memory_bytes() {
+    let mut universe = Universe::from_cells(64, 64, &[]);
+    let empty = universe.memory_bytes();
+    assert!(empty >= 64 * 64 / 8);
+
+    // The first tick builds the neighbour counts, a byte per cell.
+    universe.insert_pattern(Pattern::Glider, 1, 1);
+    universe.tick();
+    assert!(universe.memory_bytes() >= empty + 64 * 64);
+
+    let before = universe.memory_bytes();
+    universe.set_undo_limit(10);
+    for _ in 0..10 {
+        universe.tick();
     }
+    assert!(universe.memory_bytes() >= before + 10 * 64 * 64 / 8);
+}
 
-    /// Set cells to be alive in a universe by passing the row and column
-    /// of each cell as an array.
-    pub fn set_cells(&mut self, cells: &[(u32, u32)]) {
-        for (row, col) in cells.iter().cloned() {
-            let idx = self.get_index(row, col);
-            self.cells.set(idx, true);
-        }
+#[test]
+fn test_tick_stepped() {
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    universe.insert_pattern(Pattern::Glider, 1, 1);
+    let mut expected = Universe::from_cells(8, 8, &[]);
+    expected.insert_pattern(Pattern::Glider, 2, 2);
+
+    assert_eq!(universe.steps_per_call(), 1);
+    universe.set_steps_per_call(4);
+    universe.tick_stepped();
+    assert_eq!(universe.generation(), 4);
+    assert!(universe.same_cells(&expected));
+
+    universe.set_steps_per_call(0);
+    assert_eq!(universe.steps_per_call(), 1);
+    universe.tick_stepped();
+    assert_eq!(universe.generation(), 5);
+}
+
+#[test]
+fn test_classify() {
+    let mut block = Universe::from_cells(8, 8, &[(2, 2), (2, 3), (3, 2), (3, 3)]);
+    assert_eq!(block.classify(10), "still-life");
+    assert_eq!(block.generation(), 1);
+
+    let mut blinker = Universe::from_cells(8, 8, &[(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(blinker.classify(10), "oscillator(period=2)");
+
+    let mut glider = Universe::from_cells(16, 16, &[]);
+    glider.insert_pattern(Pattern::Glider, 4, 4);
+    assert_eq!(glider.classify(10), "spaceship(period=4)");
+
+    let mut spaceship = Universe::from_cells(24, 12, &[]);
+    spaceship.insert_pattern(Pattern::LightweightSpaceship, 4, 2);
+    assert_eq!(spaceship.classify(10), "spaceship(period=4)");
+
+    let mut gun = Universe::from_cells(64, 64, &[]);
+    gun.insert_pattern(Pattern::GosperGliderGun, 1, 1);
+    assert_eq!(gun.classify(60), "unknown/growing");
+}
+
+#[test]
+fn test_tick_region() {
+    let blinker = [(2, 1), (2, 2), (2, 3)];
+    let glider = [(10, 11), (11, 12), (12, 10), (12, 11), (12, 12)];
+    let cells: Vec<(u32, u32)> = blinker.iter().chain(&glider).cloned().collect();
+    let mut universe = Universe::from_cells(16, 16, &cells);
+    let mut expected = Universe::from_cells(16, 16, &cells);
+
+    // Only the blinker is updated, and the glider is left where it was.
+    universe.tick_region(0, 0, 6, 6);
+    expected.tick();
+    assert_eq!(universe.generation(), 1);
+    assert!(universe.get_cell(1, 2).unwrap() && universe.get_cell(3, 2).unwrap());
+    assert!(!universe.get_cell(2, 1).unwrap());
+    for &(row, col) in &glider {
+        assert!(universe.get_cell(row, col).unwrap());
     }
+    assert!(!universe.same_cells(&expected));
 
+    // A rectangle covering the whole board is a plain tick.
+    let mut universe = Universe::from_cells(16, 16, &cells);
+    universe.tick_region(0, 0, 100, 100);
+    assert!(universe.same_cells(&expected));
 }
 
 #[test]
-fn test_display() {
-    let mut universe = Universe::new(16, 16);
+fn test_new_from_seed_row() {
+    let universe = Universe::new_from_seed_row(7, 3, &[true, false, false]).unwrap();
+    assert_eq!(universe.live_cells(), vec![(0, 0), (0, 3), (0, 6)]);
+
+    let universe = Universe::new_from_seed_row(2, 2, &[false, true, true, true]).unwrap();
+    assert_eq!(universe.live_cells(), vec![(0, 1)]);
+
+    assert_eq!(Universe::new_from_seed_row(4, 4, &[]).unwrap().population(), 0);
+    assert!(Universe::new_from_seed_row(0, 4, &[true]).is_err());
+}
+
+#[test]
+fn test_is_complement_stable() {
+    let mut universe = Universe::from_cells(8, 8, &[(2, 1), (2, 2), (2, 3)]);
+    assert!(!universe.is_complement_stable());
+
+    universe.set_rule("B3678/S34678").unwrap();
+    assert!(universe.is_complement_stable());
+
+    let mut universe = Universe::new_hardcoded(16, 16);
+    universe.set_rule("B3678/S34678").unwrap();
+    assert!(universe.is_complement_stable());
+}
+
+#[test]
+fn test_apply_edits() {
+    let mut universe = Universe::from_cells(4, 4, &[(0, 0)]);
+    universe.apply_edits(&[(1, 1, true), (0, 0, false), (3, 2, true)]).unwrap();
+    assert_eq!(universe.live_cells(), vec![(1, 1), (3, 2)]);
+
+    let error = universe.apply_edits(&[(2, 2, true), (4, 0, true), (0, 9, true)]);
+    assert_eq!(
+        error,
+        Err(Error::OutOfBounds {
+            row: 4,
+            column: 0,
+            width: 4,
+            height: 4
+        })
+    );
+    assert_eq!(universe.live_cells(), vec![(1, 1), (3, 2)]);
+}
+
+#[test]
+fn test_tick_with_injection() {
+    let mut universe = Universe::from_cells(32, 32, &[]);
+    universe.tick_with_injection(Pattern::Glider, 0, 0, 8);
+    assert_eq!(universe.population(), 5);
+
+    // The first glider moves on, and the second is only added 8 ticks in.
+    for _ in 0..7 {
+        universe.tick_with_injection(Pattern::Glider, 0, 0, 8);
+        assert_eq!(universe.population(), 5);
+    }
+    universe.tick_with_injection(Pattern::Glider, 0, 0, 8);
+    assert_eq!(universe.population(), 10);
+    assert_eq!(universe.generation(), 9);
+}
+
+#[test]
+fn test_first_interaction() {
+    let mut glider = Universe::from_cells(32, 32, &[]);
+    glider.insert_pattern(Pattern::Glider, 0, 0);
+    assert_eq!(glider.first_interaction(40), None);
+    assert_eq!(glider.generation(), 40);
+
+    // A block in the path of the glider.
+    let mut collision = Universe::from_cells(32, 32, &[(12, 12), (12, 13), (13, 12), (13, 13)]);
+    collision.insert_pattern(Pattern::Glider, 0, 0);
+    let generation = collision.first_interaction(100).unwrap();
+    assert_eq!(collision.generation(), generation);
+    assert!(generation > 20 && generation < 100);
+}
+
+#[test]
+fn test_orientation_invariant_hash() {
+    // A glider, and its rotation by 90 degrees elsewhere on a larger board.
+    let glider = Universe::from_rows(&[".#.", "..#", "###"], '#');
+    let rotated = Universe::from_cells(10, 8, &[(3, 4), (4, 4), (4, 6), (5, 4), (5, 5)]);
+    assert_eq!(glider.orientation_invariant_hash(), rotated.orientation_invariant_hash());
+
+    let blinker = Universe::from_rows(&["###"], '#');
+    assert_ne!(glider.orientation_invariant_hash(), blinker.orientation_invariant_hash());
+}
+
+#[test]
+fn test_lockstep_divergence() {
+    let mut a = Universe::new_hardcoded(32, 32);
+    let mut b = Universe::new_hardcoded(32, 32);
+    b.toggle_cell(16, 16).unwrap();
+    let distances = lockstep_divergence(&mut a, &mut b, 10).unwrap();
+    assert_eq!(distances.len(), 11);
+    assert_eq!(distances[0], 1);
+    assert_eq!(a.generation(), 10);
+    assert_eq!(distances[10], a.hamming_distance(&b).unwrap());
+
+    let mut c = Universe::new_hardcoded(32, 16);
+    assert!(lockstep_divergence(&mut a, &mut c, 10).is_err());
+    let mut d = Universe::new_hardcoded(32, 32);
+    d.set_rule("B36/S23").unwrap();
+    assert_eq!(
+        lockstep_divergence(&mut a, &mut d, 10),
+        Err(Error::RuleMismatch { rule: "B3/S23".to_string(), other_rule: "B36/S23".to_string() })
+    );
+    assert_eq!(a.generation(), 10);
+}
+
+#[test]
+fn test_set_border() {
+    let mut universe = Universe::from_cells(5, 4, &[(1, 2), (2, 3)]);
+    universe.set_border(true);
+    assert_eq!(universe.population(), 14 + 2);
+    assert!(universe.get_cell(1, 2).unwrap() && universe.get_cell(2, 3).unwrap());
+    assert!(!universe.get_cell(1, 1).unwrap() && !universe.get_cell(2, 2).unwrap());
+
+    universe.set_border(false);
+    assert_eq!(universe.live_cells(), vec![(1, 2), (2, 3)]);
+
+    let mut column = Universe::from_cells(1, 3, &[]);
+    column.set_border(true);
+    assert_eq!(column.population(), 3);
+}
+
+#[test]
+fn test_estimate_steady_population() {
+    let universe = Universe::new_seeded(32, 32, 3);
+    let population = universe.population();
+    let estimate = universe.estimate_steady_population(4, 200, 1);
+    assert_eq!(universe.population(), population);
+    assert_eq!(universe.generation(), 0);
+    assert_eq!(estimate, universe.estimate_steady_population(4, 200, 1));
+    assert!(estimate > 0.0 && estimate < population as f64);
+
+    assert_eq!(universe.estimate_steady_population(0, 200, 1), 0.0);
+    assert_eq!(Universe::from_cells(8, 8, &[]).estimate_steady_population(3, 10, 1), 0.0);
+}
+
+#[test]
+fn test_insert_pattern_scaled() {
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    universe.insert_pattern_scaled(Pattern::Blinker, 1, 1, 2);
+    let mut expected = Universe::from_cells(8, 8, &[]);
+    expected.fill_rect(1, 1, 2, 6, true);
+    assert!(universe.same_cells(&expected));
+
+    // Clipped at the edges.
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    universe.insert_pattern_scaled(Pattern::Blinker, 6, 5, 3);
+    assert_eq!(universe.population(), 2 * 3);
+
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    universe.insert_pattern_scaled(Pattern::Glider, 0, 0, 0);
+    assert_eq!(universe.population(), 5);
+}
+
+#[test]
+fn test_row_runs() {
+    let universe = Universe::from_rows(&["..##.#", "#.....", "......"], '#');
+    assert_eq!(universe.row_runs(0), Ok(vec![2, 2, 1, 1]));
+    assert_eq!(universe.row_runs(1), Ok(vec![0, 1, 5]));
+    assert_eq!(universe.row_runs(2), Ok(vec![6]));
+    assert!(universe.row_runs(3).is_err());
+}
+
+#[test]
+fn test_mutate_weighted() {
+    let mut universe = Universe::from_cells(4, 2, &[(0, 0), (1, 3)]);
+    let weights = [1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+    universe.mutate_weighted(&weights, 1).unwrap();
+    assert_eq!(universe.live_cells(), vec![(0, 1)]);
+
+    let mut a = Universe::new_hardcoded(16, 16);
+    let mut b = Universe::new_hardcoded(16, 16);
+    a.mutate_weighted(&[0.5; 256], 9).unwrap();
+    b.mutate_weighted(&[0.5; 256], 9).unwrap();
+    assert!(a.same_cells(&b));
+    assert!(!a.same_cells(&Universe::new_hardcoded(16, 16)));
+
+    assert_eq!(
+        a.mutate_weighted(&[0.5; 10], 9),
+        Err(Error::WrongLength {
+            what: "weights",
+            expected: 256,
+            found: 10
+        })
+    );
+}
+
+#[test]
+fn test_describe_config() {
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    assert_eq!(universe.describe_config(), "Toroidal, Moore, B3/S23, radius 1");
+
+    universe.set_boundary(Boundary::Dead);
+    universe.set_rule("Life24").unwrap();
+    assert_eq!(universe.describe_config(), "Dead, Moore, Life24, radius 2");
+}
+
+#[test]
+fn test_bounding_box_history() {
+    let mut blinker = Universe::from_cells(8, 8, &[(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(blinker.bounding_box_history(3), vec![(1, 2, 3, 1), (2, 1, 1, 3), (1, 2, 3, 1)]);
+    assert_eq!(blinker.generation(), 3);
+
+    let mut dying = Universe::from_cells(8, 8, &[(2, 1), (2, 2)]);
+    assert_eq!(dying.bounding_box_history(2), vec![(0, 0, 0, 0), (0, 0, 0, 0)]);
+}
+
+#[test]
+fn test_insert_puffer_and_rake() {
+    let rightmost = |universe: &Universe| universe.live_cells().iter().map(|&(_, col)| col).max();
+
+    // The puffer moves 70 cells in 140 generations, and leaves debris
+    // behind it.
+    let mut universe = Universe::from_cells(256, 80, &[]);
+    universe.insert_puffer(30, 20);
+    assert_eq!(universe.population(), 22);
+    universe.tick_n(140);
+    assert_eq!(rightmost(&universe), Some(20 + 4 + 70));
+    assert!(universe.live_cells().iter().filter(|&&(_, col)| col < 50).count() > 100);
+
+    // The rake adds a glider every 20 generations.
+    let mut universe = Universe::from_cells(256, 64, &[]);
+    universe.insert_rake(40, 2);
+    assert_eq!(universe.population(), 65);
+    universe.tick_n(200);
+    assert_eq!(rightmost(&universe), Some(2 + 21 + 100));
+    assert_eq!(universe.population(), 65 + 10 * 5);
+    assert_eq!(universe.live_bounding_box(), Some((6, 51, 53, 73)));
+
+    // Clipped on a board too small for them.
+    let mut universe = Universe::from_cells(16, 16, &[]);
+    universe.insert_puffer(0, 0).insert_rake(4, 4);
+    assert!(universe.population() > 0);
+}
+
+#[test]
+fn test_estimate_recording_size() {
+    let mut universe = Universe::new_hardcoded(32, 32);
+    let mut copy = Universe::new_hardcoded(32, 32);
+    let keyframe = universe.to_bytes().len();
+    let deltas: usize = (0..10).map(|_| 4 * (1 + copy.tick_delta().len())).sum();
+    assert_eq!(universe.estimate_recording_size(10), keyframe + deltas);
+    assert_eq!(universe.generation(), 10);
+
+    // A still life only costs the length of each empty delta.
+    let mut block = Universe::from_cells(8, 8, &[(2, 2), (2, 3), (3, 2), (3, 3)]);
+    assert_eq!(block.estimate_recording_size(5), block.to_bytes().len() + 5 * 4);
+}
+
+#[test]
+fn test_population_autocorrelation() {
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    assert!(universe.population_autocorrelation(4).is_empty());
+    universe.insert_pattern(Pattern::Block, 2, 2);
+    universe.tick_n(10);
+    assert!(universe.population_autocorrelation(4).is_empty());
+
+    // The population of a beacon alternates between 6 and 8 cells.
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    universe.insert_pattern(Pattern::Beacon, 2, 2);
+    universe.tick_n(10);
+    assert_eq!(universe.population_autocorrelation(2), vec![1.0, -0.9, 0.8]);
+    assert_eq!(universe.population_autocorrelation(20).len(), 10);
+}
+
+#[test]
+fn test_embed_in() {
+    let mut glider = Universe::from_rows(&[".#.", "..#", "###"], '#');
+    glider.set_rule("B36/S23").unwrap();
+    let universe = glider.embed_in(10, 8, 5, 7).unwrap();
+    assert_eq!((universe.width(), universe.height()), (10, 8));
+    assert_eq!(universe.live_cells(), vec![(5, 8), (6, 9), (7, 7), (7, 8), (7, 9)]);
+    assert_eq!(universe.rule(), "B36/S23");
+    assert_eq!(glider.width(), 3);
+
+    assert_eq!(
+        glider.embed_in(10, 8, 6, 7).err(),
+        Some(Error::TooSmall {
+            width: 10,
+            height: 8,
+            min_width: 10,
+            min_height: 9
+        })
+    );
+}
+
+#[test]
+fn test_neighbor_sums() {
+    let universe = Universe::from_rows(&["....", ".##.", "...."], '#');
+    assert_eq!(universe.neighbor_sums(), vec![1, 2, 2, 1, 1, 1, 1, 1, 1, 2, 2, 1]);
+    for (idx, &sum) in universe.neighbor_sums().iter().enumerate() {
+        let (row, col) = (idx as u32 / 4, idx as u32 % 4);
+        assert_eq!(universe.neighbor_count(row, col), Ok(sum));
+    }
+}
+
+#[test]
+fn test_settle() {
+    let mut blinker = Universe::from_cells(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+    assert_eq!(blinker.settle(10), 2);
+    assert_eq!(blinker.generation(), 2);
+
+    // Three cells of a block fill in the fourth, which then stays.
+    let mut block = Universe::from_cells(6, 6, &[(2, 2), (2, 3), (3, 2)]);
+    assert_eq!(block.settle(10), 2);
+    assert_eq!(block.live_cells(), vec![(2, 2), (2, 3), (3, 2), (3, 3)]);
+
+    let mut r_pentomino = Universe::from_cells(64, 64, &[]);
+    r_pentomino.insert_pattern(Pattern::RPentomino, 30, 30);
+    assert_eq!(r_pentomino.settle(20), 20);
+    assert_eq!(r_pentomino.generation(), 20);
+}
+
+#[test]
+fn test_cluster_distances() {
+    // Blocks with centroids at (1.5, 1.5), (1.5, 5.5) and (8.5, 1.5), the
+    // last one 3 rows away across the edge.
+    let mut universe = Universe::from_cells(10, 10, &[]);
+    universe.insert_pattern(Pattern::Block, 1, 1);
+    universe.insert_pattern(Pattern::Block, 1, 5);
+    universe.insert_pattern(Pattern::Block, 8, 1);
+    assert_eq!(universe.cluster_distances(), vec![4.0, 3.0, 5.0]);
+
     universe.clear_all_cells();
-    print!("{}", universe.to_string());
-}
\ No newline at end of file
+    assert!(universe.cluster_distances().is_empty());
+}
+
+#[test]
+fn test_rule_name() {
+    let mut universe = Universe::from_cells(8, 8, &[]);
+    assert_eq!(universe.rule_name(), Some("Conway's Life".to_string()));
+    universe.set_rule("B3678/S34678").unwrap();
+    assert_eq!(universe.rule_name(), Some("Day & Night".to_string()));
+    universe.set_rule("Life24").unwrap();
+    assert_eq!(universe.rule_name(), Some("Life24".to_string()));
+    universe.set_rule("B34/S34").unwrap();
+    assert_eq!(universe.rule_name(), None);
+}
+
+#[test]
+fn test_fast_forward() {
+    let mut universe = Universe::from_cells(16, 16, &[]);
+    universe.insert_pattern(Pattern::RPentomino, 6, 6);
+    let mut expected = universe.clone();
+    expected.tick_n(40);
+
+    universe.set_undo_limit(5);
+    universe.tick();
+    let history = universe.population_history();
+    let result = universe.fast_forward(39);
+    assert!(result.same_cells(&expected));
+    assert!(universe.same_cells(&expected));
+    assert_eq!(universe.generation(), 40);
+    assert_eq!(universe.population_history(), history);
+
+    // Only the tick before the fast forward can be undone.
+    assert_eq!(universe.rewind(5), 1);
+    assert_eq!(universe.generation(), 0);
+}
+
+#[test]
+fn test_region_activity() {
+    // A blinker in the top-left 3x3 block, and a still block across the
+    // last ones, which are cut short on an 8x8 board.
+    let mut universe = Universe::from_cells(8, 8, &[(1, 0), (1, 1), (1, 2)]);
+    universe.insert_pattern(Pattern::Block, 5, 5);
+    assert_eq!(universe.region_activity(3, 2), vec![8, 0, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(universe.generation(), 2);
+
+    // Tiles of 0 are single cells, as in `heat_map`.
+    let activity = universe.region_activity(0, 1);
+    assert_eq!(activity.len(), 64);
+    assert_eq!(activity.iter().sum::<u32>(), 4);
+}
@@ -0,0 +1,210 @@
+//! Library of well-known patterns that can be inserted into a `Universe`.
+
+use wasm_bindgen::prelude::*;
+
+/// A named pattern from the library, see `Universe::insert_pattern`.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    Block = 0,
+    Beehive = 1,
+    Loaf = 2,
+    Boat = 3,
+    Blinker = 4,
+    Toad = 5,
+    Beacon = 6,
+    Glider = 7,
+    LightweightSpaceship = 8,
+    RPentomino = 9,
+    Diehard = 10,
+    Acorn = 11,
+    GosperGliderGun = 12,
+    Eater1 = 13,
+    PufferTrain = 14,
+    Spacerake = 15,
+}
+
+pub(crate) struct PatternInfo {
+    pub pattern: Pattern,
+    // Canonical LifeWiki name.
+    pub name: &'static str,
+    // `(row, column)` of each live cell, relative to the top-left corner of
+    // the pattern's bounding box.
+    pub cells: &'static [(u32, u32)],
+}
+
+// Single source of truth for the library: one entry per `Pattern` variant, in
+// the order of their discriminants, so that the list of names lines up with
+// the enum.
+pub(crate) const PATTERNS: &[PatternInfo] = &[
+    PatternInfo {
+        pattern: Pattern::Block,
+        name: "Block",
+        cells: &[(0, 0), (0, 1), (1, 0), (1, 1)],
+    },
+    PatternInfo {
+        pattern: Pattern::Beehive,
+        name: "Beehive",
+        cells: &[(0, 1), (0, 2), (1, 0), (1, 3), (2, 1), (2, 2)],
+    },
+    PatternInfo {
+        pattern: Pattern::Loaf,
+        name: "Loaf",
+        cells: &[(0, 1), (0, 2), (1, 0), (1, 3), (2, 1), (2, 3), (3, 2)],
+    },
+    PatternInfo {
+        pattern: Pattern::Boat,
+        name: "Boat",
+        cells: &[(0, 0), (0, 1), (1, 0), (1, 2), (2, 1)],
+    },
+    PatternInfo {
+        pattern: Pattern::Blinker,
+        name: "Blinker",
+        cells: &[(0, 0), (0, 1), (0, 2)],
+    },
+    PatternInfo {
+        pattern: Pattern::Toad,
+        name: "Toad",
+        cells: &[(0, 1), (0, 2), (0, 3), (1, 0), (1, 1), (1, 2)],
+    },
+    PatternInfo {
+        pattern: Pattern::Beacon,
+        name: "Beacon",
+        cells: &[(0, 0), (0, 1), (1, 0), (1, 1), (2, 2), (2, 3), (3, 2), (3, 3)],
+    },
+    PatternInfo {
+        pattern: Pattern::Glider,
+        name: "Glider",
+        cells: &[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)],
+    },
+    PatternInfo {
+        pattern: Pattern::LightweightSpaceship,
+        name: "Lightweight spaceship",
+        cells: &[
+            (0, 1), (0, 4),
+            (1, 0),
+            (2, 0), (2, 4),
+            (3, 0), (3, 1), (3, 2), (3, 3),
+        ],
+    },
+    PatternInfo {
+        pattern: Pattern::RPentomino,
+        name: "R-pentomino",
+        cells: &[(0, 1), (0, 2), (1, 0), (1, 1), (2, 1)],
+    },
+    PatternInfo {
+        pattern: Pattern::Diehard,
+        name: "Diehard",
+        cells: &[(0, 6), (1, 0), (1, 1), (2, 1), (2, 5), (2, 6), (2, 7)],
+    },
+    PatternInfo {
+        pattern: Pattern::Acorn,
+        name: "Acorn",
+        cells: &[(0, 1), (1, 3), (2, 0), (2, 1), (2, 4), (2, 5), (2, 6)],
+    },
+    PatternInfo {
+        pattern: Pattern::GosperGliderGun,
+        name: "Gosper glider gun",
+        cells: &[
+            (0, 24),
+            (1, 22), (1, 24),
+            (2, 12), (2, 13), (2, 20), (2, 21), (2, 34), (2, 35),
+            (3, 11), (3, 15), (3, 20), (3, 21), (3, 34), (3, 35),
+            (4, 0), (4, 1), (4, 10), (4, 16), (4, 20), (4, 21),
+            (5, 0), (5, 1), (5, 10), (5, 14), (5, 16), (5, 17), (5, 22), (5, 24),
+            (6, 10), (6, 16), (6, 24),
+            (7, 11), (7, 15),
+            (8, 12), (8, 13),
+        ],
+    },
+    PatternInfo {
+        pattern: Pattern::Eater1,
+        name: "Eater 1",
+        cells: &[(0, 0), (0, 1), (1, 0), (1, 2), (2, 2), (3, 2), (3, 3)],
+    },
+    PatternInfo {
+        pattern: Pattern::PufferTrain,
+        name: "Puffer train",
+        cells: &[
+            (0, 3),
+            (1, 4),
+            (2, 0), (2, 4),
+            (3, 1), (3, 2), (3, 3), (3, 4),
+            (7, 0),
+            (8, 1), (8, 2),
+            (9, 2),
+            (10, 2),
+            (11, 1),
+            (14, 3),
+            (15, 4),
+            (16, 0), (16, 4),
+            (17, 1), (17, 2), (17, 3), (17, 4),
+        ],
+    },
+    PatternInfo {
+        pattern: Pattern::Spacerake,
+        name: "Spacerake",
+        cells: &[
+            (0, 11), (0, 12), (0, 18), (0, 19), (0, 20), (0, 21),
+            (1, 9), (1, 10), (1, 12), (1, 13), (1, 17), (1, 21),
+            (2, 9), (2, 10), (2, 11), (2, 12), (2, 21),
+            (3, 10), (3, 11), (3, 17), (3, 20),
+            (5, 8),
+            (6, 7), (6, 8), (6, 17), (6, 18),
+            (7, 6), (7, 16), (7, 19),
+            (8, 7), (8, 8), (8, 9), (8, 10), (8, 11), (8, 16), (8, 19),
+            (9, 8), (9, 9), (9, 10), (9, 11), (9, 15), (9, 16), (9, 18), (9, 19),
+            (10, 11), (10, 16), (10, 17),
+            (14, 18), (14, 19), (14, 20), (14, 21),
+            (15, 0), (15, 3), (15, 17), (15, 21),
+            (16, 4), (16, 21),
+            (17, 0), (17, 4), (17, 17), (17, 20),
+            (18, 1), (18, 2), (18, 3), (18, 4),
+        ],
+    },
+];
+
+impl Pattern {
+    pub(crate) fn info(self) -> &'static PatternInfo {
+        PATTERNS
+            .iter()
+            .find(|info| info.pattern == self)
+            .expect("every pattern has an entry in PATTERNS")
+    }
+
+    /// Canonical LifeWiki name of the pattern.
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+
+    /// `(row, column)` of each live cell, relative to the top-left corner of
+    /// the pattern.
+    pub fn cells(self) -> &'static [(u32, u32)] {
+        self.info().cells
+    }
+
+    /// Size of the smallest rectangle holding the pattern, as
+    /// `(rows, columns)`, e.g. to check that it fits before inserting it.
+    pub fn dimensions(self) -> (u32, u32) {
+        self.cells()
+            .iter()
+            .fold((0, 0), |(rows, cols), &(r, c)| (rows.max(r + 1), cols.max(c + 1)))
+    }
+}
+
+#[test]
+fn test_patterns_are_indexed_by_discriminant() {
+    for (i, info) in PATTERNS.iter().enumerate() {
+        assert_eq!(info.pattern as usize, i, "{}", info.name);
+    }
+}
+
+#[test]
+fn test_pattern_dimensions() {
+    assert_eq!(Pattern::Blinker.dimensions(), (1, 3));
+    assert_eq!(Pattern::Glider.dimensions(), (3, 3));
+    assert_eq!(Pattern::LightweightSpaceship.dimensions(), (4, 5));
+    assert_eq!(Pattern::GosperGliderGun.dimensions(), (9, 36));
+    assert_eq!(Pattern::PufferTrain.dimensions(), (18, 5));
+    assert_eq!(Pattern::Spacerake.dimensions(), (19, 22));
+}
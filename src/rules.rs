@@ -0,0 +1,148 @@
+//! Parsing and formatting of outer-totalistic rules in B/S notation, e.g.
+//! `B3/S23` for Conway's game of life.
+//!
+//! A rule is represented by two masks: bit `n` of the birth mask is set if a
+//! dead cell with `n` live neighbours becomes alive, and bit `n` of the
+//! survival mask is set if a live cell with `n` live neighbours stays alive.
+
+/// Name of a rule that extends Life to a radius 2 neighbourhood: the 24
+/// cells of the 5x5 square around a cell, wrapping around the edges like
+/// the 8 of the B/S rules. A dead cell with 8 or 9 live neighbours is born,
+/// and a live cell with 6 to 9 live neighbours survives.
+///
+/// It has no B/S notation, since counts above 8 have no digit. Its masks are
+/// `LIFE24_BIRTH` and `LIFE24_SURVIVAL`.
+pub const LIFE24: &str = "Life24";
+pub const LIFE24_BIRTH: u32 = 1 << 8 | 1 << 9;
+pub const LIFE24_SURVIVAL: u32 = 1 << 6 | 1 << 7 | 1 << 8 | 1 << 9;
+
+/// Parse a rule such as `B3/S23`, returning its `(birth, survival)` masks.
+///
+/// The `B` and `S` parts may come in either order and in either case. The
+/// older `S/B` notation without letters, such as `23/3`, is also accepted.
+pub fn parse(rule: &str) -> Option<(u32, u32)> {
+    let rule = rule.trim();
+    let parts: Vec<&str> = rule.split('/').map(str::trim).collect();
+    if parts.len() != 2 {
+        return None;
+    }
+
+    let (mut birth, mut survival) = (None, None);
+    for part in parts.iter() {
+        let mut chars = part.chars();
+        match chars.next().map(|c| c.to_ascii_uppercase()) {
+            Some('B') if birth.is_none() => birth = Some(parse_digits(chars.as_str())?),
+            Some('S') if survival.is_none() => survival = Some(parse_digits(chars.as_str())?),
+            _ => break,
+        }
+    }
+
+    match (birth, survival) {
+        (Some(birth), Some(survival)) => Some((birth, survival)),
+        (None, None) => Some((parse_digits(parts[1])?, parse_digits(parts[0])?)),
+        _ => None,
+    }
+}
+
+// Turn a list of neighbour counts, such as `23`, into a mask.
+fn parse_digits(digits: &str) -> Option<u32> {
+    let mut mask = 0;
+    for digit in digits.chars() {
+        match digit.to_digit(10) {
+            Some(n) if n <= 8 => mask |= 1 << n,
+            _ => return None,
+        }
+    }
+    Some(mask)
+}
+
+/// Format rule masks in the canonical `B3/S23` notation.
+pub fn format(birth: u32, survival: u32) -> String {
+    let digits = |mask: u32| -> String {
+        (0..=8)
+            .filter(|n| mask & (1 << n) != 0)
+            .map(|n| std::char::from_digit(n, 10).unwrap())
+            .collect()
+    };
+    format!("B{}/S{}", digits(birth), digits(survival))
+}
+
+// Well-known rules, by their canonical notation.
+const NAMED_RULES: [(&str, &str); 10] = [
+    ("B3/S23", "Conway's Life"),
+    ("B36/S23", "HighLife"),
+    ("B3678/S34678", "Day & Night"),
+    ("B2/S", "Seeds"),
+    ("B3/S012345678", "Life without Death"),
+    ("B1357/S1357", "Replicator"),
+    ("B3/S12345", "Maze"),
+    ("B368/S245", "Morley"),
+    ("B36/S125", "2x2"),
+    ("B35678/S5678", "Diamoeba"),
+];
+
+/// The usual name of the rule with these masks, e.g. `HighLife` for
+/// `B36/S23`, or `None` if it is not one of the well-known ones.
+pub fn name(birth: u32, survival: u32) -> Option<&'static str> {
+    let rule = format(birth, survival);
+    NAMED_RULES.iter().find(|&&(notation, _)| notation == rule).map(|&(_, name)| name)
+}
+
+/// Describe rule masks in plain English, e.g. `A cell is born with 3
+/// neighbors; survives with 2 or 3 neighbors` for `B3/S23`.
+pub fn describe(birth: u32, survival: u32) -> String {
+    let counts = |mask: u32| -> String {
+        let counts: Vec<String> = (0..32).filter(|n| mask & (1 << n) != 0).map(|n| n.to_string()).collect();
+        let unit = if counts == ["1"] { "neighbor" } else { "neighbors" };
+        match counts.split_last() {
+            None => String::new(),
+            Some((last, [])) => format!("{} {}", last, unit),
+            Some((last, rest)) => format!("{} or {} {}", rest.join(", "), last, unit),
+        }
+    };
+
+    let born = if birth == 0 { "is never born".to_string() } else { format!("is born with {}", counts(birth)) };
+    let survives = if survival == 0 {
+        "never survives".to_string()
+    } else {
+        format!("survives with {}", counts(survival))
+    };
+    format!("A cell {}; {}", born, survives)
+}
+
+#[test]
+fn test_parse_rules() {
+    let conway = Some((1 << 3, 1 << 2 | 1 << 3));
+    assert_eq!(parse("B3/S23"), conway);
+    assert_eq!(parse("s23/b3"), conway);
+    assert_eq!(parse("23/3"), conway);
+    assert_eq!(parse("B36/S23").map(|(b, s)| format(b, s)), Some("B36/S23".to_string()));
+    assert_eq!(parse("B/S"), Some((0, 0)));
+
+    assert_eq!(parse("B3"), None);
+    assert_eq!(parse("B9/S23"), None);
+    assert_eq!(parse("B3/B23"), None);
+    assert_eq!(parse("B3/23"), None);
+}
+
+#[test]
+fn test_describe_rules() {
+    assert_eq!(describe(1 << 3, 1 << 2 | 1 << 3), "A cell is born with 3 neighbors; survives with 2 or 3 neighbors");
+    assert_eq!(
+        describe(1 << 3 | 1 << 6, 1 << 1),
+        "A cell is born with 3 or 6 neighbors; survives with 1 neighbor"
+    );
+    assert_eq!(describe(0, LIFE24_SURVIVAL), "A cell is never born; survives with 6, 7, 8 or 9 neighbors");
+    assert_eq!(describe(1 << 2, 0), "A cell is born with 2 neighbors; never survives");
+}
+
+#[test]
+fn test_rule_names() {
+    assert_eq!(name(1 << 3, 1 << 2 | 1 << 3), Some("Conway's Life"));
+    assert_eq!(parse("S23/B63").and_then(|(b, s)| name(b, s)), Some("HighLife"));
+    assert_eq!(parse("B2/S").and_then(|(b, s)| name(b, s)), Some("Seeds"));
+    assert_eq!(parse("B34/S34").and_then(|(b, s)| name(b, s)), None);
+    for &(notation, _) in &NAMED_RULES {
+        assert_eq!(parse(notation).map(|(b, s)| format(b, s)), Some(notation.to_string()));
+    }
+}
@@ -0,0 +1,158 @@
+//! Recording of the edits made to a universe as a script, which can be
+//! replayed to build the same board again.
+//!
+//! A script has one edit per line: the name of the editing method followed
+//! by its arguments, separated by spaces, with booleans written as `0` or
+//! `1` and patterns by their name in the `Pattern` enum, e.g.
+//! `insert_pattern Glider 3 4`.
+
+use wasm_bindgen::prelude::*;
+
+use crate::patterns::PATTERNS;
+use crate::{Error, Pattern, Universe};
+
+#[wasm_bindgen]
+impl Universe {
+    /// Start or stop recording edits into the script returned by
+    /// `export_script`.
+    ///
+    /// The edits recorded are those made with `set_cell`, `toggle_cell`,
+    /// `insert_pattern`, `insert_pattern_wrapped`, `fill_rect`,
+    /// `toggle_rect`, `draw_line`, `clear_all_cells` and `set_rule`, along
+    /// with the methods built on them. Ticks are not recorded, and neither
+    /// is anything random.
+    pub fn set_recording(&mut self, recording: bool) {
+        self.recording = recording;
+    }
+
+    /// The edits recorded so far, one per line, see `set_recording`.
+    pub fn export_script(&self) -> String {
+        self.script.iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    /// Forget the edits recorded so far.
+    pub fn clear_script(&mut self) {
+        self.script.clear();
+    }
+
+    /// Replay a script produced by `export_script`, usually on a fresh
+    /// universe of the same size. Blank lines and lines starting with `#`
+    /// are skipped.
+    ///
+    /// Fails on the first line that is not a valid edit, or whose edit
+    /// fails, after applying the lines before it.
+    pub fn apply_script(&mut self, script: &str) -> Result<(), Error> {
+        for (i, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || Error::InvalidScript {
+                line: i + 1,
+                text: line.to_string(),
+            };
+
+            let mut words = line.split_whitespace();
+            let edit = words.next().ok_or_else(invalid)?;
+            let args: Vec<&str> = words.collect();
+            let numbers = |count: usize| -> Result<Vec<u32>, Error> {
+                if args.len() != count {
+                    return Err(invalid());
+                }
+                args.iter().map(|arg| arg.parse().map_err(|_| invalid())).collect()
+            };
+            let flag = |value: u32| match value {
+                0 => Ok(false),
+                1 => Ok(true),
+                _ => Err(invalid()),
+            };
+
+            match edit {
+                "set_cell" => {
+                    let n = numbers(3)?;
+                    self.set_cell(n[0], n[1], flag(n[2])?)?;
+                }
+                "toggle_cell" => {
+                    let n = numbers(2)?;
+                    self.toggle_cell(n[0], n[1])?;
+                }
+                "insert_pattern" | "insert_pattern_wrapped" => {
+                    let (name, rest) = args.split_first().ok_or_else(invalid)?;
+                    let pattern = pattern_named(name).ok_or_else(invalid)?;
+                    let n: Vec<u32> = rest
+                        .iter()
+                        .map(|arg| arg.parse().map_err(|_| invalid()))
+                        .collect::<Result<_, _>>()?;
+                    if n.len() != 2 {
+                        return Err(invalid());
+                    }
+                    if edit == "insert_pattern" {
+                        self.insert_pattern(pattern, n[0], n[1]);
+                    } else {
+                        self.insert_pattern_wrapped(pattern, n[0], n[1]);
+                    }
+                }
+                "fill_rect" => {
+                    let n = numbers(5)?;
+                    self.fill_rect(n[0], n[1], n[2], n[3], flag(n[4])?);
+                }
+                "toggle_rect" => {
+                    let n = numbers(4)?;
+                    self.toggle_rect(n[0], n[1], n[2], n[3]);
+                }
+                "draw_line" => {
+                    let n = numbers(4)?;
+                    self.draw_line(n[0], n[1], n[2], n[3]);
+                }
+                "clear_all_cells" if args.is_empty() => self.clear_all_cells(),
+                "set_rule" if args.len() == 1 => self.set_rule(args[0])?,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Universe {
+    // Append an edit to the script if recording, only formatting it then.
+    pub(crate) fn record(&mut self, edit: impl FnOnce() -> String) {
+        if self.recording {
+            self.script.push(edit());
+        }
+    }
+}
+
+// The pattern whose `Pattern` variant is called `name`.
+fn pattern_named(name: &str) -> Option<Pattern> {
+    PATTERNS
+        .iter()
+        .map(|info| info.pattern)
+        .find(|pattern| format!("{:?}", pattern) == name)
+}
+
+#[test]
+fn test_script_round_trip() {
+    let mut universe = Universe::from_cells(16, 16, &[]);
+    universe.set_recording(true);
+    universe.insert_pattern(Pattern::Glider, 2, 3);
+    universe.fill_rect(10, 10, 2, 3, true);
+    universe.toggle_rect(9, 11, 2, 4);
+    universe.insert_pattern_wrapped(Pattern::Block, 15, 6);
+    universe.draw_line(0, 15, 5, 12);
+    universe.toggle_cell(11, 11).unwrap();
+    universe.set_rule("B36/S23").unwrap();
+    universe.set_recording(false);
+    universe.set_cell(0, 0, true).unwrap();
+
+    let script = universe.export_script();
+    assert_eq!(script.lines().next(), Some("insert_pattern Glider 2 3"));
+    assert_eq!(script.lines().count(), 7);
+
+    let mut replayed = Universe::from_cells(16, 16, &[]);
+    replayed.apply_script(&script).unwrap();
+    assert_eq!(replayed.population() + 1, universe.population());
+    assert_eq!(replayed.rule(), "B36/S23");
+
+    let error = replayed.apply_script("# comment\n\nset_cell 1 2\n").unwrap_err();
+    assert_eq!(error, Error::InvalidScript { line: 3, text: "set_cell 1 2".to_string() });
+}
@@ -1,3 +1,25 @@
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// Log levels for the `log!` macro, see `set_log_level`. Messages at
+// `LOG_ALWAYS` are logged whatever the level, for logging that is already
+// opted into some other way.
+pub const LOG_ALWAYS: u8 = 0;
+pub const LOG_INFO: u8 = 1;
+pub const LOG_DEBUG: u8 = 2;
+pub const LOG_TRACE: u8 = 3;
+
+// Current log level; logging is off by default.
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_log_level(level: u8) {
+    LOG_LEVEL.store(level, Ordering::Relaxed);
+}
+
+pub fn log_enabled(level: u8) -> bool {
+    level <= LOG_LEVEL.load(Ordering::Relaxed)
+}
+
 pub fn set_panic_hook() {
     // When the `console_error_panic_hook` feature is enabled, we can call the
     // `set_panic_hook` function at least once during initialization, and then
@@ -8,3 +30,50 @@ pub fn set_panic_hook() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
 }
+
+// Small, seedable pseudo-random number generator (SplitMix64), used wherever a
+// reproducible stream of random numbers is needed instead of
+// `js_sys::Math::random`, which is neither seedable nor available outside of
+// wasm.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniformly distributed in `[0, 1)`, like `Math.random()`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+thread_local! {
+    // Generator used by `random` instead of `Math.random()`, if any.
+    static SEEDED_RNG: RefCell<Option<Rng>> = const { RefCell::new(None) };
+}
+
+// Make `random` draw from a generator seeded with `seed`, or from
+// `Math.random()` again with `None`.
+pub fn set_seeded_rng(seed: Option<u64>) {
+    SEEDED_RNG.with(|rng| *rng.borrow_mut() = seed.map(Rng::new));
+}
+
+// Uniformly distributed in `[0, 1)`, from the generator set with
+// `set_seeded_rng`, or else `Math.random()`.
+pub fn random() -> f64 {
+    SEEDED_RNG.with(|rng| match rng.borrow_mut().as_mut() {
+        Some(rng) => rng.next_f64(),
+        None => js_sys::Math::random(),
+    })
+}
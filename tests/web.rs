@@ -2,6 +2,7 @@
 
 #![cfg(target_arch = "wasm32")]
 
+extern crate js_sys;
 extern crate wasm_bindgen_test;
 extern crate wasm_game_of_life;
 
@@ -40,4 +41,17 @@ pub fn test_tick() {
     // Call `tick` and then see if the cells in the `Universe`s are the same.
     input_universe.tick();
     assert_eq!(input_universe.get_cells(), expected_universe.get_cells());
-}
\ No newline at end of file
+}
+
+#[wasm_bindgen_test]
+pub fn test_to_grid() {
+    let universe = Universe::from_cells(3, 2, &[(0, 1), (1, 2)]);
+    let grid = js_sys::Array::from(&universe.to_grid());
+    assert_eq!(grid.length(), 2);
+
+    let rows: Vec<Vec<f64>> = grid
+        .iter()
+        .map(|row| js_sys::Array::from(&row).iter().map(|cell| cell.as_f64().unwrap()).collect())
+        .collect();
+    assert_eq!(rows, vec![vec![0.0, 1.0, 0.0], vec![0.0, 0.0, 1.0]]);
+}